@@ -0,0 +1,121 @@
+// Render a GraphRequest JSON file to PNG or SVG without the Workers runtime.
+//
+//   chart-render request.json -o chart.png
+//   cat request.json | chart-render --format svg > chart.svg
+
+use rust_chart_worker::models::OutputFormat;
+use std::io::{Read, Write};
+use std::path::PathBuf;
+use std::process::ExitCode;
+
+const USAGE: &str = "Usage: chart-render [OPTIONS] [INPUT]
+
+Arguments:
+  [INPUT]                GraphRequest JSON file, or - for stdin (default: stdin)
+
+Options:
+  -o, --output <PATH>    Write the image to PATH instead of stdout
+  -f, --format <FORMAT>  png or svg (default: the output file extension, then the request)
+  -h, --help             Print this help";
+
+struct Args {
+    input: Option<PathBuf>,
+    output: Option<PathBuf>,
+    format: Option<OutputFormat>,
+}
+
+fn parse_format(value: &str) -> Option<OutputFormat> {
+    match value.to_ascii_lowercase().as_str() {
+        "png" => Some(OutputFormat::Png),
+        "svg" => Some(OutputFormat::Svg),
+        _ => None,
+    }
+}
+
+fn parse_args() -> Result<Option<Args>, String> {
+    let mut args = Args {
+        input: None,
+        output: None,
+        format: None,
+    };
+    let mut iter = std::env::args().skip(1);
+
+    while let Some(arg) = iter.next() {
+        match arg.as_str() {
+            "-h" | "--help" => return Ok(None),
+            "-o" | "--output" => {
+                let path = iter.next().ok_or("missing value for --output")?;
+                args.output = Some(PathBuf::from(path));
+            }
+            "-f" | "--format" => {
+                let value = iter.next().ok_or("missing value for --format")?;
+                args.format = Some(parse_format(&value).ok_or_else(|| {
+                    format!("unsupported format: {} (expected png or svg)", value)
+                })?);
+            }
+            "-" => args.input = None,
+            _ if arg.starts_with('-') => return Err(format!("unknown option: {}", arg)),
+            _ if args.input.is_some() => return Err(format!("unexpected argument: {}", arg)),
+            _ => args.input = Some(PathBuf::from(arg)),
+        }
+    }
+
+    Ok(Some(args))
+}
+
+fn run(args: Args) -> Result<(), String> {
+    let body = match &args.input {
+        Some(path) => {
+            std::fs::read(path).map_err(|e| format!("cannot read {}: {}", path.display(), e))?
+        }
+        None => {
+            let mut body = Vec::new();
+            std::io::stdin()
+                .read_to_end(&mut body)
+                .map_err(|e| format!("cannot read stdin: {}", e))?;
+            body
+        }
+    };
+
+    let mut request = rust_chart_worker::request::parse_json(&body).map_err(|e| e.to_json())?;
+    let extension_format = args
+        .output
+        .as_ref()
+        .and_then(|path| path.extension())
+        .and_then(|ext| parse_format(&ext.to_string_lossy()));
+    if let Some(format) = args.format.or(extension_format) {
+        request.format = format;
+    }
+
+    let rendered = rust_chart_worker::render(&request).map_err(|e| e.to_json())?;
+
+    match &args.output {
+        Some(path) => std::fs::write(path, &rendered.body)
+            .map_err(|e| format!("cannot write {}: {}", path.display(), e)),
+        None => std::io::stdout()
+            .write_all(&rendered.body)
+            .map_err(|e| format!("cannot write to stdout: {}", e)),
+    }
+}
+
+fn main() -> ExitCode {
+    let args = match parse_args() {
+        Ok(Some(args)) => args,
+        Ok(None) => {
+            println!("{}", USAGE);
+            return ExitCode::SUCCESS;
+        }
+        Err(e) => {
+            eprintln!("chart-render: {}\n\n{}", e, USAGE);
+            return ExitCode::from(2);
+        }
+    };
+
+    match run(args) {
+        Ok(()) => ExitCode::SUCCESS,
+        Err(e) => {
+            eprintln!("chart-render: {}", e);
+            ExitCode::FAILURE
+        }
+    }
+}
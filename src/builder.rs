@@ -0,0 +1,266 @@
+use crate::error::ChartError;
+use crate::models::{
+    Bins, GraphRequest, GraphType, LegendPosition, Matrix, Orientation, OutputFormat, PaletteName,
+    ScaleType, Series, Stacking, ThemeSpec,
+};
+
+/// Builds a [`GraphRequest`] in Rust and renders it in-process, producing the same
+/// images as the worker.
+///
+/// ```
+/// use rust_chart_worker::ChartBuilder;
+/// use rust_chart_worker::models::Series;
+///
+/// let png = ChartBuilder::line()
+///     .title("Team Performance")
+///     .series(Series::new("Team A").data([("Q1", 30.0), ("Q2", 40.0), ("Q3", 35.0)]))
+///     .series(Series::new("Team B").data([20.0, 25.0, 30.0]))
+///     .size(640.0, 480.0)
+///     .render_png()
+///     .unwrap();
+/// assert!(png.starts_with(b"\x89PNG"));
+/// ```
+///
+/// The request can also be sent to a deployed worker instead:
+///
+/// ```
+/// # use rust_chart_worker::ChartBuilder;
+/// let json = ChartBuilder::bar().data([1.0, 2.0, 3.0]).to_json().unwrap();
+/// let request = rust_chart_worker::request::parse_json(json.as_bytes()).unwrap();
+/// assert_eq!(&request, ChartBuilder::bar().data([1.0, 2.0, 3.0]).request());
+/// ```
+#[derive(Debug, Clone, Default)]
+pub struct ChartBuilder {
+    request: GraphRequest,
+}
+
+impl ChartBuilder {
+    /// Starts an empty chart of the given type.
+    pub fn new(graph_type: GraphType) -> Self {
+        ChartBuilder {
+            request: GraphRequest {
+                graph_type,
+                ..GraphRequest::default()
+            },
+        }
+    }
+
+    pub fn line() -> Self {
+        ChartBuilder::new(GraphType::Line)
+    }
+
+    pub fn bar() -> Self {
+        ChartBuilder::new(GraphType::Bar)
+    }
+
+    pub fn scatter() -> Self {
+        ChartBuilder::new(GraphType::Scatter)
+    }
+
+    pub fn bubble() -> Self {
+        ChartBuilder::new(GraphType::Bubble)
+    }
+
+    pub fn pie() -> Self {
+        ChartBuilder::new(GraphType::Pie)
+    }
+
+    pub fn donut() -> Self {
+        ChartBuilder::new(GraphType::Donut)
+    }
+
+    pub fn area() -> Self {
+        ChartBuilder::new(GraphType::Area)
+    }
+
+    pub fn radar() -> Self {
+        ChartBuilder::new(GraphType::Radar)
+    }
+
+    pub fn heatmap() -> Self {
+        ChartBuilder::new(GraphType::Heatmap)
+    }
+
+    /// Daily values laid out by week; label each point with its date (`YYYY-MM-DD`).
+    pub fn calendar() -> Self {
+        ChartBuilder::new(GraphType::Calendar)
+    }
+
+    /// Distribution of raw values; see [`ChartBuilder::bins`] and [`ChartBuilder::density`].
+    pub fn histogram() -> Self {
+        ChartBuilder::new(GraphType::Histogram)
+    }
+
+    /// A box per series, from its values or a [`Summary`](crate::models::Summary).
+    pub fn boxplot() -> Self {
+        ChartBuilder::new(GraphType::Box)
+    }
+
+    /// Daily prices of the first series; give each point open, high, low and close
+    /// with [`DataPoint::ohlc`](crate::models::DataPoint::ohlc).
+    pub fn candlestick() -> Self {
+        ChartBuilder::new(GraphType::Candlestick)
+    }
+
+    /// Like [`ChartBuilder::candlestick`], drawn as OHLC bars.
+    pub fn ohlc() -> Self {
+        ChartBuilder::new(GraphType::Ohlc)
+    }
+
+    pub fn title(mut self, title: impl Into<String>) -> Self {
+        self.request.title = Some(title.into());
+        self
+    }
+
+    pub fn x_label(mut self, label: impl Into<String>) -> Self {
+        self.request.x_label = Some(label.into());
+        self
+    }
+
+    pub fn y_label(mut self, label: impl Into<String>) -> Self {
+        self.request.y_label = Some(label.into());
+        self
+    }
+
+    /// Adds a series; call repeatedly for multi-series charts.
+    pub fn series(mut self, series: Series) -> Self {
+        self.request.series.push(series);
+        self
+    }
+
+    /// Plain values without series, like the `data` field of a JSON request. Use
+    /// [`ChartBuilder::series`] for labels, per-point colors or x positions.
+    pub fn data(mut self, values: impl IntoIterator<Item = f64>) -> Self {
+        self.request.data = values.into_iter().collect();
+        self
+    }
+
+    /// Cells of a heatmap, see [`Matrix`].
+    pub fn matrix(mut self, matrix: Matrix) -> Self {
+        self.request.matrix = Some(matrix);
+        self
+    }
+
+    /// How a histogram bins its values: a [`BinRule`](crate::models::BinRule), a bin count such as `20` or
+    /// [`Bins::width`].
+    pub fn bins(mut self, bins: impl Into<Bins>) -> Self {
+        self.request.bins = Some(bins.into());
+        self
+    }
+
+    /// Overlays a smoothed density curve on a histogram.
+    pub fn density(mut self, density: bool) -> Self {
+        self.request.density = density;
+        self
+    }
+
+    /// Marks the mean of each box in a box plot.
+    pub fn show_mean(mut self, show: bool) -> Self {
+        self.request.show_mean = show;
+        self
+    }
+
+    /// Colors used by series (or pie slices) that do not set their own.
+    pub fn colors<S: Into<String>>(mut self, colors: impl IntoIterator<Item = S>) -> Self {
+        self.request.colors = Some(colors.into_iter().map(Into::into).collect());
+        self
+    }
+
+    /// Canvas size in pixels.
+    pub fn size(mut self, width: f64, height: f64) -> Self {
+        self.request.width = Some(width);
+        self.request.height = Some(height);
+        self
+    }
+
+    /// Device pixel ratio for PNG output, e.g. `2.0` for retina displays.
+    pub fn scale(mut self, scale: f64) -> Self {
+        self.request.scale = Some(scale);
+        self
+    }
+
+    pub fn x_scale(mut self, scale: ScaleType) -> Self {
+        self.request.x_scale = scale;
+        self
+    }
+
+    pub fn y_scale(mut self, scale: ScaleType) -> Self {
+        self.request.y_scale = scale;
+        self
+    }
+
+    pub fn stacking(mut self, stacking: Stacking) -> Self {
+        self.request.stacking = stacking;
+        self
+    }
+
+    pub fn orientation(mut self, orientation: Orientation) -> Self {
+        self.request.orientation = orientation;
+        self
+    }
+
+    /// Where the legend goes, or [`LegendPosition::None`] to hide it.
+    pub fn legend(mut self, position: LegendPosition) -> Self {
+        self.request.legend = position;
+        self
+    }
+
+    /// A preset such as [`ThemePreset::Dark`](crate::models::ThemePreset) or a
+    /// [`CustomTheme`](crate::models::CustomTheme) overriding parts of one.
+    pub fn theme(mut self, theme: impl Into<ThemeSpec>) -> Self {
+        self.request.theme = theme.into();
+        self
+    }
+
+    /// Replaces the theme's series colors with a named palette. Sequential and
+    /// diverging palettes are spread evenly over the series (or pie slices).
+    pub fn palette(mut self, palette: PaletteName) -> Self {
+        self.request.palette = Some(palette);
+        self
+    }
+
+    /// Output format used by [`ChartBuilder::render`] and the worker.
+    pub fn format(mut self, format: OutputFormat) -> Self {
+        self.request.format = format;
+        self
+    }
+
+    pub fn request(&self) -> &GraphRequest {
+        &self.request
+    }
+
+    pub fn into_request(self) -> GraphRequest {
+        self.request
+    }
+
+    /// The request as JSON, accepted by the worker's POST endpoint.
+    pub fn to_json(&self) -> Result<String, ChartError> {
+        serde_json::to_string(&self.request).map_err(|e| ChartError::Render {
+            message: format!("Failed to serialize the request: {e}"),
+        })
+    }
+
+    /// Renders in the format set with [`ChartBuilder::format`] (PNG by default).
+    pub fn render(&self) -> Result<crate::Rendered, ChartError> {
+        crate::render(&self.request)
+    }
+
+    pub fn render_png(&self) -> Result<Vec<u8>, ChartError> {
+        self.clone()
+            .format(OutputFormat::Png)
+            .render()
+            .map(|r| r.body)
+    }
+
+    /// Renders to SVG with all text converted to paths, like the worker's `format=svg`.
+    pub fn render_svg(&self) -> Result<String, ChartError> {
+        let rendered = self.clone().format(OutputFormat::Svg).render()?;
+        Ok(String::from_utf8(rendered.body).expect("SVG output is UTF-8"))
+    }
+}
+
+impl From<GraphRequest> for ChartBuilder {
+    fn from(request: GraphRequest) -> Self {
+        ChartBuilder { request }
+    }
+}
@@ -1,41 +1,14 @@
-use super::{get_max_value, Chart};
-use crate::models::GraphRequest;
-use crate::utils;
+use super::Chart;
+use crate::models::{GraphRequest, Stacking};
+use crate::utils::{color, svg};
 
 pub struct AreaChart {}
 
 impl Chart for AreaChart {
     fn generate(&self, request: &GraphRequest) -> String {
-        let mut svg_content = String::from(
-            r#"<?xml version="1.0" encoding="UTF-8" standalone="no"?>
-<svg width="800" height="600" xmlns="http://www.w3.org/2000/svg">
-<rect width="800" height="600" fill="white"/>"#,
-        );
-
-        // Draw title and labels first
-        if let Some(title) = &request.title {
-            svg_content.push_str(&format!(
-                r#"<text x="400" y="30" text-anchor="middle" font-family="M PLUS 1p" font-size="20">{}</text>"#,
-                title
-            ));
-        }
-
-        if let Some(x_label) = request.x_label.as_deref() {
-            svg_content.push_str(&format!(
-                r#"<text x="400" y="580" text-anchor="middle" font-family="M PLUS 1p" font-size="14">{}</text>"#,
-                x_label
-            ));
-        }
-
-        if let Some(y_label) = request.y_label.as_deref() {
-            svg_content.push_str(&format!(
-                r#"<text x="30" y="300" text-anchor="middle" font-family="M PLUS 1p" font-size="14" transform="rotate(-90, 30, 300)">{}</text>"#,
-                y_label
-            ));
-        }
-
+        let (theme, legend, layout) = super::frame(request);
         let series = if request.series.is_empty() {
-            vec![request.data.iter().map(|&v| v).collect::<Vec<f64>>()]
+            vec![request.data.to_vec()]
         } else {
             request
                 .series
@@ -44,88 +17,109 @@ impl Chart for AreaChart {
                 .collect()
         };
 
-        let raw_max = get_max_value(&request.series);
-        let max_value = utils::svg::nice_max(raw_max);
-        let segment_width = 640.0 / (series[0].len() as f64 - 1.0);
-
-        // Draw areas first
-        svg_content.push_str(
-            r#"<g transform="translate(80, 50)">"#,
+        let stacked = super::stack_values(&series, request.stacking);
+        // The domain covers whole stacks, not just individual values
+        let (min_value, max_value) =
+            super::min_max(stacked.iter().flatten().flat_map(|&(a, b)| [a, b]));
+        let y_scale = super::value_scale(
+            request,
+            min_value,
+            max_value,
+            (layout.plot_bottom, layout.plot_top),
+        );
+        let layout = svg::fit_y_ticks(layout, &theme, &y_scale, request.y_label.is_some());
+        let x_axis = super::XAxis::new(request, &layout);
+
+        let mut svg_content = svg::create_svg_header(
+            &layout,
+            &theme,
+            request.title.as_deref(),
+            request.x_label.as_deref(),
+            request.y_label.as_deref(),
         );
 
+        svg_content.push_str(&svg::generate_y_axis_ticks(&layout, &theme, &y_scale));
+        svg_content.push_str(&svg::generate_zero_line(&layout, &theme, &y_scale));
+        svg_content.push_str(&x_axis.ticks(request, &layout, &theme));
+
+        let is_stacked = request.stacking != Stacking::None;
+        let baseline = y_scale.baseline();
+        // Points and values go on top of every area so later layers cannot hide them
+        let mut markers = String::new();
+
         // Draw areas
         for (series_idx, series_data) in series.iter().enumerate() {
-            let color = request
-                .colors
-                .as_ref()
-                .and_then(|c| c.get(series_idx))
-                .map(String::as_str)
-                .unwrap_or(
-                    utils::get_default_colors()[series_idx % utils::get_default_colors().len()],
-                );
+            let color = color::resolve(&theme, request, series_idx, None);
+            let segments = &stacked[series_idx];
+            if segments.is_empty() {
+                continue;
+            }
 
-            // Create path for area
+            // Top edge left to right, then back along the bottom edge: the zero line
+            // for overlaid areas, or the top of the previous layer when stacking
             let mut path = String::new();
-            path.push_str(&format!("M 0 450")); // Start at bottom-left
-
-            // Draw top line
-            for (i, &value) in series_data.iter().enumerate() {
-                let x = i as f64 * segment_width;
-                let y = 450.0 - ((value / max_value) * 400.0);
-                if i == 0 {
-                    path.push_str(&format!(" L {:.1} {:.1}", x, y));
+            for (i, &(_, end)) in segments.iter().enumerate() {
+                let command = if i == 0 { "M" } else { " L" };
+                path.push_str(&format!(
+                    "{} {:.1} {:.1}",
+                    command,
+                    x_axis.position(request, &layout, series_idx, i),
+                    y_scale.map(end)
+                ));
+            }
+            for (i, &(start, _)) in segments.iter().enumerate().rev() {
+                let y = if is_stacked {
+                    y_scale.map(start)
                 } else {
-                    path.push_str(&format!(" L {:.1} {:.1}", x, y));
-                }
+                    baseline
+                };
+                path.push_str(&format!(
+                    " L {:.1} {:.1}",
+                    x_axis.position(request, &layout, series_idx, i),
+                    y
+                ));
             }
-
-            // Complete the path back to the bottom
-            path.push_str(&format!(
-                " L {:.1} 450",
-                (series_data.len() - 1) as f64 * segment_width
-            ));
             path.push_str(" Z"); // Close the path
 
             svg_content.push_str(&format!(
-                r#"<path d="{}" fill="{}" fill-opacity="0.3"/>"#,
-                path, color
+                r#"<path d="{}" fill="{}" fill-opacity="{}"/>"#,
+                path,
+                color,
+                if is_stacked { 0.7 } else { 0.3 }
             ));
 
             // Add data points and values
-            for (i, &value) in series_data.iter().enumerate() {
-                let x = i as f64 * segment_width;
-                let y = 450.0 - ((value / max_value) * 400.0);
-                svg_content.push_str(&format!(
+            for (i, (&value, &(start, end))) in series_data.iter().zip(segments).enumerate() {
+                let x = x_axis.position(request, &layout, series_idx, i);
+                let y = y_scale.map(end);
+                markers.push_str(&format!(
                     r#"<circle cx="{}" cy="{}" r="4" fill="{}"/>"#,
                     x, y, color
                 ));
-                svg_content.push_str(&utils::svg::generate_value_text(x, y, value));
+                if is_stacked {
+                    markers.push_str(&format!(
+                        r#"<text x="{}" y="{}" text-anchor="middle" font-family="M PLUS 1p" font-size="{}">{}</text>"#,
+                        x,
+                        y - 5.0,
+                        theme.tick_font_size,
+                        super::stacked_value_text(value, start, end, request.stacking)
+                    ));
+                } else {
+                    markers.push_str(&svg::generate_value_text(
+                        &theme,
+                        x,
+                        y,
+                        value,
+                        &y_scale.format_value(value),
+                    ));
+                }
             }
         }
+        svg_content.push_str(&markers);
 
-        // Draw grid lines and axes last (on top)
-        svg_content.push_str(
-            r#"<line x1="0" y1="450" x2="640" y2="450" stroke="black" stroke-width="2"/>
-<line x1="0" y1="0" x2="0" y2="450" stroke="black" stroke-width="2"/>"#,
-        );
-
-        // Add Y-axis labels
-        let y_ticks = 5;
-        for i in 0..=y_ticks {
-            let y = 450.0 - (i as f64 * 400.0 / y_ticks as f64);
-            let value = (i as f64 / y_ticks as f64) * max_value;
-            svg_content.push_str(&format!(
-                r#"<text x="-10" y="{:.1}" text-anchor="end" font-family="M PLUS 1p" font-size="12">{:.1}</text>"#,
-                y, value
-            ));
-        }
-
-        // Legend to the right
-        if !request.series.is_empty() {
-            svg_content.push_str(&crate::utils::svg::create_legend(&request.series, 660.0, 50.0));
-        }
+        svg_content.push_str(&legend.render(&layout, (layout.origin_x, layout.origin_y)));
 
-        svg_content.push_str("</g></svg>");
+        svg_content.push_str(svg::create_svg_footer());
         svg_content
     }
 }
@@ -1,137 +1,292 @@
-use crate::models::{GraphRequest, Series};
-use crate::utils::{self, svg};
+use crate::models::{GraphRequest, Orientation, Series, Stacking};
+use crate::utils::{color, layout::Layout, legend::Legend, scale::Scale, svg, text, theme::Theme};
 
 pub struct BarChart {}
 
 impl super::Chart for BarChart {
     fn generate(&self, request: &GraphRequest) -> String {
+        let (theme, legend, layout) = super::frame(request);
         let default_series = if request.series.is_empty() {
-            let default_color = utils::get_default_colors()[0].to_string();
-            let series_data = request
-                .data
-                .iter()
-                .map(|&value| crate::models::DataPoint {
-                    value,
-                    label: None,
-                    color: None,
-                })
-                .collect();
-            vec![Series {
-                name: None,
-                data: series_data,
-                color: Some(default_color),
-            }]
+            vec![Series::default().data(request.data.iter().copied())]
         } else {
             request.series.clone()
         };
 
-        let raw_max = default_series
+        let values: Vec<Vec<f64>> = default_series
             .iter()
-            .flat_map(|s| s.data.iter().map(|d| d.value))
-            .fold(f64::NEG_INFINITY, f64::max);
-        let max_value = svg::nice_max(raw_max);
+            .map(|s| s.data.iter().map(|d| d.value).collect())
+            .collect();
+        let stacked = super::stack_values(&values, request.stacking);
+        // The domain covers whole stacks, not just individual values
+        let (min_value, max_value) =
+            super::min_max(stacked.iter().flatten().flat_map(|&(a, b)| [a, b]));
 
-        // Match the drawable width used by axes (0..640)
-        let segment_width = 640.0 / (default_series[0].data.len() as f64);
-        // Group width inside each segment; center the group at the tick.
-        let bar_group_width = if default_series.len() > 1 {
-            segment_width * 0.7 // multi-series: slightly wider group
-        } else {
-            segment_width * 0.8 // single series: comfortable width
+        let bars = Bars {
+            request,
+            theme: &theme,
+            legend: &legend,
+            layout,
+            series: &default_series,
+            stacked: &stacked,
+            is_stacked: request.stacking != Stacking::None,
         };
+        match request.orientation {
+            Orientation::Vertical => bars.generate_vertical(min_value, max_value),
+            Orientation::Horizontal => bars.generate_horizontal(min_value, max_value),
+        }
+    }
+}
 
-        let mut svg_content = format!(
-            r#"<svg width="800" height="600" xmlns="http://www.w3.org/2000/svg">
-            <rect width="100%" height="100%" fill="white"/>
-            <g transform="translate(80, 50)">"#
-        );
+struct Bars<'a> {
+    request: &'a GraphRequest,
+    theme: &'a Theme,
+    legend: &'a Legend,
+    layout: Layout,
+    series: &'a [Series],
+    stacked: &'a [Vec<(f64, f64)>],
+    is_stacked: bool,
+}
 
-        // Add title if provided
-        if let Some(title) = &request.title {
-            svg_content.push_str(&format!(
-                r#"<text x="320" y="30" text-anchor="middle" font-family="M PLUS 1p" font-size="20">{}</text>"#,
-                title
-            ));
+impl Bars<'_> {
+    // Thickness of the bar group within one category slot
+    fn group_size(&self, segment: f64) -> f64 {
+        if self.series.len() > 1 && !self.is_stacked {
+            segment * 0.7 // multi-series: slightly wider group
+        } else {
+            segment * 0.8 // single series: comfortable width
         }
+    }
 
-        // Add x-axis label if provided
-        if let Some(x_label) = &request.x_label {
-            svg_content.push_str(&format!(
-                r#"<text x="320" y="520" text-anchor="middle" font-family="M PLUS 1p" font-size="14">{}</text>"#,
-                x_label
-            ));
+    // Stacked bars share the full group; grouped bars split it
+    fn bar_size(&self, group: f64) -> f64 {
+        if self.is_stacked {
+            group
+        } else {
+            group / self.series.len() as f64
         }
+    }
 
-        // Add y-axis label if provided
-        if let Some(y_label) = &request.y_label {
-            svg_content.push_str(&format!(
-                r#"<text x="-280" y="-50" text-anchor="middle" font-family="M PLUS 1p" font-size="14" transform="rotate(-90)">{}</text>"#,
-                y_label
-            ));
+    // Offset of a series' bar from the start of its group
+    fn bar_offset(&self, series_idx: usize, bar_size: f64) -> f64 {
+        if self.is_stacked {
+            0.0
+        } else {
+            series_idx as f64 * bar_size
         }
+    }
 
-        // Draw axes
-        svg_content.push_str(
-            r#"<line x1="0" y1="450" x2="640" y2="450" stroke="black" stroke-width="2"/>
-            <line x1="0" y1="50" x2="0" y2="450" stroke="black" stroke-width="2"/>"#,
+    // Pixel positions of a bar's start and end along the value axis
+    fn bar_extent(&self, scale: &Scale, series_idx: usize, i: usize) -> (f64, f64, f64, f64) {
+        let (start, end) = self.stacked[series_idx][i];
+        // Bars grow from the zero line (or the stack below) away from zero
+        let start_px = if self.is_stacked {
+            scale.map(start)
+        } else {
+            scale.baseline()
+        };
+        (start, end, start_px, scale.map(end))
+    }
+
+    fn generate_vertical(&self, min_value: f64, max_value: f64) -> String {
+        let request = self.request;
+        let theme = self.theme;
+        let layout = self.layout;
+        let y_scale = super::value_scale(
+            request,
+            min_value,
+            max_value,
+            (layout.plot_bottom, layout.plot_top),
+        );
+        let layout = svg::fit_y_ticks(layout, theme, &y_scale, request.y_label.is_some());
+
+        // Match the drawable width used by axes
+        let segment_width = layout.plot_width / super::category_count(request) as f64;
+        // Group width inside each segment; center the group at the tick.
+        let bar_group_width = self.group_size(segment_width);
+        let bar_each_width = self.bar_size(bar_group_width);
+
+        let mut svg_content = svg::create_svg_header(
+            &layout,
+            theme,
+            request.title.as_deref(),
+            request.x_label.as_deref(),
+            request.y_label.as_deref(),
         );
 
         // Draw y-axis ticks and values
-        for i in 0..=5 {
-            let y = 450.0 - (i as f64 * 80.0);
-            let value = (i as f64 * max_value / 5.0).round();
-            svg_content.push_str(&format!(
-                r#"<line x1="-5" y1="{}" x2="0" y2="{}" stroke="black" stroke-width="1"/>
-                <text x="-10" y="{}" text-anchor="end" font-family="M PLUS 1p" font-size="12">{}</text>"#,
-                y, y, y + 4.0, utils::format_number(value)
-            ));
-        }
+        svg_content.push_str(&svg::generate_y_axis_ticks(&layout, theme, &y_scale));
+        svg_content.push_str(&svg::generate_zero_line(&layout, theme, &y_scale));
 
         // Draw x-axis ticks and values
-        svg_content.push_str(&utils::svg::generate_x_axis_ticks_for_bar(default_series[0].data.len()));
+        svg_content.push_str(&svg::generate_x_axis_ticks_for_bar(
+            &layout,
+            theme,
+            &super::category_labels(request),
+        ));
 
         // Draw bars
-        for (series_idx, series_item) in default_series.iter().enumerate() {
-            let bar_each_width = bar_group_width / default_series.len() as f64;
-
+        for (series_idx, series_item) in self.series.iter().enumerate() {
             for (i, point) in series_item.data.iter().enumerate() {
+                let color = color::resolve(theme, request, series_idx, Some(i));
                 // Group centered at segment center
-                let group_left = (i as f64 * segment_width) + (segment_width - bar_group_width) / 2.0;
-                let x = group_left + series_idx as f64 * bar_each_width;
-                let height = (point.value / max_value) * 400.0;
-                let y = 450.0 - height;
-                let color = match &point.color {
-                    Some(c) => c.clone(),
-                    None => match &series_item.color {
-                        Some(c) => c.clone(),
-                        None => "#0000FF".to_string(),
-                    },
-                };
+                let group_left =
+                    (i as f64 * segment_width) + (segment_width - bar_group_width) / 2.0;
+                let x = group_left + self.bar_offset(series_idx, bar_each_width);
+                let (start, end, start_y, value_y) = self.bar_extent(&y_scale, series_idx, i);
+                let y = value_y.min(start_y);
+                let height = (value_y - start_y).abs();
 
                 svg_content.push_str(&format!(
                     r#"<rect x="{}" y="{}" width="{}" height="{}" fill="{}"/>"#,
-                    x,
-                    y,
-                    bar_each_width,
-                    height,
-                    color
+                    x, y, bar_each_width, height, color
                 ));
 
-                svg_content.push_str(&utils::svg::generate_value_text(
-                    x + bar_each_width / 2.0,
-                    y,
-                    point.value,
-                ));
+                if self.is_stacked {
+                    // Label inside the segment when there is room for the text
+                    if height >= 14.0 {
+                        svg_content.push_str(&format!(
+                            r#"<text x="{}" y="{}" text-anchor="middle" font-family="M PLUS 1p" font-size="{}">{}</text>"#,
+                            x + bar_each_width / 2.0,
+                            y + height / 2.0 + 4.0,
+                            theme.tick_font_size,
+                            super::stacked_value_text(point.value, start, end, request.stacking)
+                        ));
+                    }
+                } else {
+                    svg_content.push_str(&svg::generate_value_text(
+                        theme,
+                        x + bar_each_width / 2.0,
+                        value_y,
+                        point.value,
+                        &y_scale.format_value(point.value),
+                    ));
+                }
             }
         }
 
-        // Add legend if there are multiple series
-        if default_series.len() > 1 {
-            // Place legend outside plotting area to avoid overlap with tall bars
-            svg_content.push_str(&svg::create_legend(&default_series, 660.0, 50.0));
+        svg_content.push_str(
+            &self
+                .legend
+                .render(&layout, (layout.origin_x, layout.origin_y)),
+        );
+        svg_content.push_str(svg::create_svg_footer());
+        svg_content
+    }
+
+    // Categories run top to bottom along the left edge and bars grow to the right,
+    // which leaves room for long category names
+    fn generate_horizontal(&self, min_value: f64, max_value: f64) -> String {
+        let request = self.request;
+        let theme = self.theme;
+        let font_size = theme.tick_font_size;
+        let labels = super::category_labels(request);
+
+        // Widen the left margin to fit the longest category name and the y-axis label
+        let widest = labels
+            .iter()
+            .map(|l| text::text_width(l, font_size))
+            .fold(0.0, f64::max);
+        let layout = svg::fit_left_labels(self.layout, theme, widest, request.y_label.is_some());
+        let max_label = svg::left_label_room(&layout, theme, request.y_label.is_some());
+
+        let x_scale = super::value_scale(request, min_value, max_value, (0.0, layout.plot_width));
+
+        let segment_height = (layout.plot_bottom - layout.plot_top) / labels.len() as f64;
+        let bar_group_height = self.group_size(segment_height);
+        let bar_each_height = self.bar_size(bar_group_height);
+
+        let mut svg_content = svg::create_svg_header(
+            &layout,
+            theme,
+            request.title.as_deref(),
+            request.x_label.as_deref(),
+            request.y_label.as_deref(),
+        );
+
+        // Value ticks along the bottom
+        svg_content.push_str(&svg::generate_x_axis_ticks_for_scale(
+            &layout, theme, &x_scale,
+        ));
+        if x_scale.crosses_zero() {
+            svg_content.push_str(&format!(
+                r#"<line x1="{x}" y1="{}" x2="{x}" y2="{}" stroke="{}" stroke-width="{}"/>"#,
+                layout.plot_top,
+                layout.plot_bottom,
+                theme.axis,
+                theme.axis_width / 2.0,
+                x = x_scale.baseline()
+            ));
         }
 
-        svg_content.push_str("</g></svg>");
+        // Category labels to the left of the axis
+        for (i, label) in labels.iter().enumerate() {
+            let y = layout.plot_top + (i as f64 + 0.5) * segment_height;
+            svg_content.push_str(&format!(
+                r#"<line x1="-5" y1="{y}" x2="0" y2="{y}" stroke="{}" stroke-width="{}"/>
+            <text x="-10" y="{}" text-anchor="end" font-family="M PLUS 1p" font-size="{}">{}</text>"#,
+                theme.axis,
+                theme.axis_width,
+                y + font_size / 3.0,
+                font_size,
+                svg::escape_text(&text::truncate_to_width(label, max_label, font_size))
+            ));
+        }
+
+        // Draw bars
+        for (series_idx, series_item) in self.series.iter().enumerate() {
+            for (i, point) in series_item.data.iter().enumerate() {
+                let color = color::resolve(theme, request, series_idx, Some(i));
+                let group_top = layout.plot_top
+                    + i as f64 * segment_height
+                    + (segment_height - bar_group_height) / 2.0;
+                let y = group_top + self.bar_offset(series_idx, bar_each_height);
+                let (start, end, start_x, value_x) = self.bar_extent(&x_scale, series_idx, i);
+                let x = value_x.min(start_x);
+                let width = (value_x - start_x).abs();
+
+                svg_content.push_str(&format!(
+                    r#"<rect x="{}" y="{}" width="{}" height="{}" fill="{}"/>"#,
+                    x, y, width, bar_each_height, color
+                ));
+
+                let text_y = y + bar_each_height / 2.0 + 4.0;
+                if self.is_stacked {
+                    let value_text =
+                        super::stacked_value_text(point.value, start, end, request.stacking);
+                    if width >= text::text_width(&value_text, font_size) + 4.0 {
+                        svg_content.push_str(&format!(
+                            r#"<text x="{}" y="{}" text-anchor="middle" font-family="M PLUS 1p" font-size="{}">{}</text>"#,
+                            x + width / 2.0,
+                            text_y,
+                            font_size,
+                            value_text
+                        ));
+                    }
+                } else {
+                    // Value just past the end of the bar
+                    let (text_x, anchor) = if point.value < 0.0 {
+                        (value_x - 5.0, "end")
+                    } else {
+                        (value_x + 5.0, "start")
+                    };
+                    svg_content.push_str(&format!(
+                        r#"<text x="{}" y="{}" text-anchor="{}" font-family="M PLUS 1p" font-size="{}">{}</text>"#,
+                        text_x,
+                        text_y,
+                        anchor,
+                        font_size,
+                        svg::escape_text(&x_scale.format_value(point.value))
+                    ));
+                }
+            }
+        }
+
+        svg_content.push_str(
+            &self
+                .legend
+                .render(&layout, (layout.origin_x, layout.origin_y)),
+        );
+        svg_content.push_str(svg::create_svg_footer());
         svg_content
     }
 }
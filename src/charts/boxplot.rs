@@ -0,0 +1,228 @@
+use super::Chart;
+use crate::models::{GraphRequest, Summary};
+use crate::utils::{color, scale, svg};
+
+// Few boxes would otherwise grow as wide as half the plot
+const MAX_BOX_WIDTH: f64 = 80.0;
+
+pub struct BoxPlotChart {}
+
+// What a box shows: whisker ends in min/max, the points beyond them and the mean
+pub struct Stats {
+    pub summary: Summary,
+    pub outliers: Vec<f64>,
+}
+
+// Tukey's box: quartiles, whiskers to the furthest values within 1.5 IQR of the box
+// and everything further out as outliers
+pub fn stats(values: &[f64]) -> Option<Stats> {
+    if values.is_empty() {
+        return None;
+    }
+    let mut sorted = values.to_vec();
+    sorted.sort_by(f64::total_cmp);
+    let q1 = super::quantile(&sorted, 0.25);
+    let q3 = super::quantile(&sorted, 0.75);
+    // 1.5 IQR from half of it, as q3 - q1 can overflow. Should the reach still pass
+    // f64::MAX, the fences are infinite and every value lies inside them.
+    let reach = scale::split(q1, q3, 2.0) * 3.0;
+    let (low, high) = (q1 - reach, q3 + reach);
+    let inside = || sorted.iter().copied().filter(|&v| v >= low && v <= high);
+    let n = sorted.len() as f64;
+    let sum: f64 = sorted.iter().sum();
+    let mean = if sum.is_finite() {
+        sum / n
+    } else {
+        sorted.iter().map(|v| v / n).sum()
+    };
+    Some(Stats {
+        summary: Summary {
+            min: inside().fold(q1, f64::min),
+            q1,
+            median: super::quantile(&sorted, 0.5),
+            q3,
+            max: inside().fold(q3, f64::max),
+            mean: Some(mean),
+        },
+        outliers: sorted
+            .iter()
+            .copied()
+            .filter(|&v| v < low || v > high)
+            .collect(),
+    })
+}
+
+// A box per series (the plain `data` array is one), from its summary when given and
+// from its values otherwise. None where a series has nothing to show.
+pub fn boxes(request: &GraphRequest) -> Vec<Option<Stats>> {
+    if request.series.is_empty() {
+        return vec![stats(&request.data)];
+    }
+    request
+        .series
+        .iter()
+        .map(|s| match s.summary {
+            Some(summary) => {
+                // Tolerate summaries given out of order
+                let mut five = [
+                    summary.min,
+                    summary.q1,
+                    summary.median,
+                    summary.q3,
+                    summary.max,
+                ];
+                five.sort_by(f64::total_cmp);
+                let [min, q1, median, q3, max] = five;
+                Some(Stats {
+                    summary: Summary {
+                        min,
+                        q1,
+                        median,
+                        q3,
+                        max,
+                        mean: summary.mean,
+                    },
+                    outliers: Vec::new(),
+                })
+            }
+            None => stats(&s.data.iter().map(|d| d.value).collect::<Vec<_>>()),
+        })
+        .collect()
+}
+
+impl Chart for BoxPlotChart {
+    fn generate(&self, request: &GraphRequest) -> String {
+        let (theme, legend, layout) = super::frame(request);
+        let boxes = boxes(request);
+        let labels: Vec<String> = (0..boxes.len())
+            .map(|i| {
+                request
+                    .series
+                    .get(i)
+                    .and_then(|s| s.name.clone())
+                    .unwrap_or_else(|| (i + 1).to_string())
+            })
+            .collect();
+
+        let (min_value, max_value) = super::min_max(boxes.iter().flatten().flat_map(|b| {
+            [b.summary.min, b.summary.max]
+                .into_iter()
+                .chain(b.summary.mean.filter(|_| request.show_mean))
+                .chain(b.outliers.iter().copied())
+        }));
+        let y_scale = super::value_scale(
+            request,
+            min_value,
+            max_value,
+            (layout.plot_bottom, layout.plot_top),
+        );
+        let layout = svg::fit_y_ticks(layout, &theme, &y_scale, request.y_label.is_some());
+
+        let mut svg_content = svg::create_svg_header(
+            &layout,
+            &theme,
+            request.title.as_deref(),
+            request.x_label.as_deref(),
+            request.y_label.as_deref(),
+        );
+        svg_content.push_str(&svg::generate_y_axis_ticks(&layout, &theme, &y_scale));
+        svg_content.push_str(&svg::generate_zero_line(&layout, &theme, &y_scale));
+        svg_content.push_str(&svg::generate_x_axis_ticks_for_bar(
+            &layout, &theme, &labels,
+        ));
+
+        let segment_width = layout.plot_width / boxes.len() as f64;
+        let box_width = (segment_width * 0.5).min(MAX_BOX_WIDTH);
+        for (i, stats) in boxes.iter().enumerate() {
+            let Some(Stats { summary, outliers }) = stats else {
+                continue;
+            };
+            let color = color::resolve(&theme, request, i, None);
+            let center = (i as f64 + 0.5) * segment_width;
+            let (left, right) = (center - box_width / 2.0, center + box_width / 2.0);
+            let y = |value: f64| y_scale.map(value);
+
+            // Whiskers with caps half as wide as the box
+            for (from, to) in [(summary.q1, summary.min), (summary.q3, summary.max)] {
+                svg_content.push_str(&format!(
+                    r#"<line x1="{center}" y1="{}" x2="{center}" y2="{y2}" stroke="{color}" stroke-width="{w}"/><line x1="{}" y1="{y2}" x2="{}" y2="{y2}" stroke="{color}" stroke-width="{w}"/>"#,
+                    y(from),
+                    center - box_width / 4.0,
+                    center + box_width / 4.0,
+                    y2 = y(to),
+                    w = theme.line_width,
+                ));
+            }
+            svg_content.push_str(&format!(
+                r#"<rect x="{}" y="{}" width="{}" height="{}" fill="{color}" fill-opacity="0.3" stroke="{color}" stroke-width="{}"/>"#,
+                left,
+                y(summary.q3),
+                box_width,
+                y(summary.q1) - y(summary.q3),
+                theme.line_width,
+            ));
+            svg_content.push_str(&format!(
+                r#"<line x1="{left}" y1="{m}" x2="{right}" y2="{m}" stroke="{color}" stroke-width="{}"/>"#,
+                theme.line_width * 2.0,
+                m = y(summary.median),
+            ));
+
+            for &outlier in outliers {
+                svg_content.push_str(&format!(
+                    r#"<circle cx="{center}" cy="{}" r="3" fill="none" stroke="{color}" stroke-width="1.5"/>"#,
+                    y(outlier)
+                ));
+            }
+
+            // The mean as a diamond, so it is not mistaken for an outlier
+            if let Some(mean) = summary.mean.filter(|_| request.show_mean) {
+                let m = y(mean);
+                svg_content.push_str(&format!(
+                    r#"<path d="M {center} {} L {} {m} L {center} {} L {} {m} Z" fill="{}" stroke="{}" stroke-width="1.5"/>"#,
+                    m - 5.0,
+                    center + 5.0,
+                    m + 5.0,
+                    center - 5.0,
+                    theme.background,
+                    theme.foreground,
+                ));
+            }
+        }
+
+        svg_content.push_str(&legend.render(&layout, (layout.origin_x, layout.origin_y)));
+        svg_content.push_str(svg::create_svg_footer());
+        svg_content
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn whiskers_stop_at_the_last_value_within_reach() {
+        let stats = stats(&[1.0, 2.0, 3.0, 4.0, 5.0, 6.0, 7.0, 8.0, 30.0]).unwrap();
+        let summary = stats.summary;
+        assert_eq!((summary.q1, summary.median, summary.q3), (3.0, 5.0, 7.0));
+        // 1.5 IQR above the box is 13, so 30 is an outlier and the whisker ends at 8
+        assert_eq!((summary.min, summary.max), (1.0, 8.0));
+        assert_eq!(stats.outliers, [30.0]);
+        assert_eq!(summary.mean, Some(66.0 / 9.0));
+    }
+
+    #[test]
+    fn largest_values_keep_a_finite_box() {
+        for values in [
+            &[-1e308, 1e308][..],
+            &[f64::MIN, 0.0, f64::MAX],
+            &[1e308, 1e308],
+        ] {
+            let stats = stats(values).unwrap();
+            let s = stats.summary;
+            let all = [s.min, s.q1, s.median, s.q3, s.max, s.mean.unwrap()];
+            assert!(all.iter().all(|v| v.is_finite()), "{values:?}: {all:?}");
+            // The fences reach past the extremes, so none of them is an outlier
+            assert!(stats.outliers.is_empty(), "{values:?}");
+        }
+    }
+}
@@ -0,0 +1,230 @@
+use super::heatmap;
+use super::Chart;
+use crate::models::GraphRequest;
+use crate::utils::date::{self, MONTH_NAMES};
+use crate::utils::{layout::Layout, svg, text};
+
+// Older days are dropped beyond this, about ten years
+const MAX_DAYS: i64 = 3_660;
+const MAX_CELL: f64 = 24.0;
+// Weekday names on the left, for every other row as on GitHub (rows start on Sunday)
+const WEEKDAY_LABELS: [(u32, &str); 3] = [(1, "Mon"), (3, "Wed"), (5, "Fri")];
+
+pub struct CalendarChart {}
+
+// (days since 1970-01-01, value) for every point with a date, sorted and with values
+// on the same day added up. A point's date is its label as YYYY-MM-DD, or its x as a
+// Unix timestamp in seconds within years 0 to 9999.
+pub fn days(request: &GraphRequest) -> Vec<(i64, f64)> {
+    let mut days: Vec<(i64, f64)> = request
+        .series
+        .iter()
+        .flat_map(|s| s.data.iter())
+        .filter_map(|d| {
+            let day = d
+                .label
+                .as_deref()
+                .and_then(date::parse_iso_date)
+                .or_else(|| d.x.and_then(date::day_from_timestamp))?;
+            Some((day, d.value))
+        })
+        .collect();
+    days.sort_by_key(|&(day, _)| day);
+    days.dedup_by(|next, kept| {
+        if next.0 == kept.0 {
+            kept.1 += next.1;
+            true
+        } else {
+            false
+        }
+    });
+    if let Some(&(last, _)) = days.last() {
+        days.retain(|&(day, _)| day > last - MAX_DAYS);
+    }
+    days
+}
+
+impl Chart for CalendarChart {
+    fn generate(&self, request: &GraphRequest) -> String {
+        let (theme, legend, layout) = super::frame(request);
+        let days = days(request);
+        let font_size = theme.tick_font_size;
+        let k = layout.margin_ratio();
+
+        // Whole weeks from the Sunday before the first day to the Saturday after the last
+        let first = days.first().map_or(0, |&(day, _)| day);
+        let last = days.last().map_or(0, |&(day, _)| day);
+        let start = first - date::weekday(first) as i64;
+        let weeks = ((last - start) / 7 + 1) as usize;
+
+        let ramp = heatmap::ramp(request);
+        let color_scale = heatmap::color_scale(&ramp, days.iter().map(|&(_, v)| v));
+
+        // There is nothing below the grid but the x-axis label, so it may use the space
+        // of the tick labels
+        let widest_weekday = WEEKDAY_LABELS
+            .iter()
+            .map(|(_, label)| text::text_width(label, font_size))
+            .fold(0.0, f64::max);
+        let layout =
+            svg::fit_left_labels(layout, &theme, widest_weekday, request.y_label.is_some());
+        let layout = layout.with_right_margin(
+            layout
+                .right_margin()
+                .max(heatmap::color_scale_width(&layout, &theme, &color_scale)),
+        );
+        let x_label_row = match request.x_label {
+            Some(_) => text::line_height(theme.label_font_size) + 10.0 * k,
+            None => 0.0,
+        };
+        let bottom = layout.free_bottom - layout.origin_y - 20.0 * k - x_label_row;
+        let month_row = text::line_height(font_size);
+        let grid_top = layout.plot_top + month_row;
+        let cell = (layout.plot_width / weeks as f64)
+            .min((bottom - grid_top) / 7.0)
+            .clamp(1.0, MAX_CELL);
+        let gap = (cell * 0.15).min(3.0);
+
+        let grid_bottom = grid_top + 7.0 * cell;
+        let grid_width = weeks as f64 * cell;
+
+        // The y-axis label is centered on the grid rather than the whole plot area
+        let grid_layout = Layout {
+            plot_top: grid_top,
+            plot_bottom: grid_bottom,
+            ..layout
+        };
+        let mut svg_content = svg::create_svg_header_labels(
+            &grid_layout,
+            &theme,
+            request.title.as_deref(),
+            None,
+            request.y_label.as_deref(),
+        );
+        // and the x-axis label sits right below the grid, as there are no ticks
+        if let Some(x_label) = &request.x_label {
+            let center = grid_width / 2.0;
+            svg_content.push_str(&format!(
+                r#"<text x="{}" y="{}" text-anchor="middle" font-family="M PLUS 1p" font-size="{}">{}</text>"#,
+                center,
+                grid_bottom + 10.0 * k + theme.label_font_size,
+                theme.label_font_size,
+                svg::escape_text(&text::truncate_to_width(
+                    x_label,
+                    layout.plot_width.min(2.0 * (center + layout.origin_x)),
+                    theme.label_font_size
+                ))
+            ));
+        }
+
+        // Days in the range without a value are drawn empty
+        let mut values = days.iter().peekable();
+        for day in start..start + weeks as i64 * 7 {
+            let week = (day - start) / 7;
+            let value = match values.peek() {
+                Some(&&(d, v)) if d == day => {
+                    values.next();
+                    Some(v)
+                }
+                _ => None,
+            };
+            if value.is_none() && (day < first || day > last) {
+                continue;
+            }
+            let fill = match value {
+                Some(v) => ramp.at(color_scale.map(v)),
+                None => theme.grid.clone(),
+            };
+            svg_content.push_str(&format!(
+                r#"<rect x="{}" y="{}" width="{s}" height="{s}" rx="{}" fill="{}" />"#,
+                week as f64 * cell,
+                grid_top + date::weekday(day) as f64 * cell,
+                gap,
+                fill,
+                s = cell - gap,
+            ));
+        }
+
+        // Month names above the week that holds the 1st, skipped where they would overlap
+        let mut next_free = f64::NEG_INFINITY;
+        for week in 0..weeks {
+            let week_start = start + week as i64 * 7;
+            let month_start = (week_start..week_start + 7)
+                .find(|&day| day >= first && date::civil_from_days(day).2 == 1)
+                .or((week == 0).then_some(first));
+            let Some(day) = month_start else {
+                continue;
+            };
+            let (year, month, _) = date::civil_from_days(day);
+            // January shows the year instead
+            let label = if month == 1 {
+                year.to_string()
+            } else {
+                MONTH_NAMES[month as usize - 1].to_string()
+            };
+            let x = week as f64 * cell;
+            if x < next_free {
+                continue;
+            }
+            next_free = x + text::text_width(&label, font_size) + 6.0;
+            svg_content.push_str(&format!(
+                r#"<text x="{}" y="{}" font-family="M PLUS 1p" font-size="{}">{}</text>"#,
+                x,
+                grid_top - month_row / 2.0 + font_size / 3.0,
+                font_size,
+                label
+            ));
+        }
+
+        // Only where every other row leaves room for the text
+        let weekday_labels = if 2.0 * cell >= font_size {
+            &WEEKDAY_LABELS[..]
+        } else {
+            &[]
+        };
+        for &(row, label) in weekday_labels {
+            svg_content.push_str(&format!(
+                r#"<text x="-6" y="{}" text-anchor="end" font-family="M PLUS 1p" font-size="{}" fill="{}">{}</text>"#,
+                grid_top + (row as f64 + 0.5) * cell - gap / 2.0 + font_size / 3.0,
+                font_size,
+                theme.muted,
+                label
+            ));
+        }
+
+        svg_content.push_str(&heatmap::draw_color_scale(
+            &theme,
+            &ramp,
+            &color_scale,
+            (weeks as f64 * cell + 20.0 * k, grid_top),
+            7.0 * cell - gap,
+        ));
+
+        svg_content.push_str(&legend.render(&layout, (layout.origin_x, layout.origin_y)));
+        svg_content.push_str(svg::create_svg_footer());
+        svg_content
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    #[test]
+    fn days_come_from_labels_or_timestamps() {
+        let request: GraphRequest = serde_json::from_value(json!({
+            "graph_type": "calendar",
+            "series": [{ "data": [
+                { "label": "2024-01-02", "value": 1.0 },
+                { "label": "not a date", "value": 5.0 },
+                { "x": 1704153600.0 + 3600.0, "value": 2.0 },
+                { "label": "2024-01-01T23:00:00Z", "value": 4.0 },
+                { "x": 1e300, "value": 6.0 },
+            ] }],
+        }))
+        .unwrap();
+        // 2024-01-01 is day 19723; the timestamp falls on 2024-01-02 and 1e300 on no day
+        assert_eq!(days(&request), [(19723, 4.0), (19724, 3.0)]);
+    }
+}
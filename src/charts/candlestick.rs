@@ -0,0 +1,217 @@
+use super::Chart;
+use crate::models::{GraphRequest, Ohlc, ScaleType};
+use crate::utils::{color, scale::Scale, svg, text};
+
+// Share of the plot height for the volume panel, and the gap above it
+const VOLUME_SHARE: f64 = 0.2;
+const VOLUME_GAP: f64 = 0.05;
+// A handful of candles would otherwise turn into wide blocks
+const MAX_BODY_WIDTH: f64 = 40.0;
+
+// Candlesticks, or OHLC bars with ticks for the open (left) and close (right)
+pub struct CandlestickChart {
+    pub is_ohlc: bool,
+}
+
+pub struct Candle {
+    pub prices: Ohlc,
+    pub volume: Option<f64>,
+    pub color: Option<String>,
+}
+
+// Candles of the first series by category slot; None where a point has no prices
+pub fn candles(request: &GraphRequest) -> Vec<Option<Candle>> {
+    let Some(series) = request.series.first() else {
+        return Vec::new();
+    };
+    series
+        .data
+        .iter()
+        .map(|d| {
+            // High and low enclose the open and close even when the input does not
+            let Ohlc {
+                open,
+                high,
+                low,
+                close,
+            } = d.ohlc?;
+            Some(Candle {
+                prices: Ohlc {
+                    open,
+                    high: high.max(open).max(close),
+                    low: low.min(open).min(close),
+                    close,
+                },
+                volume: d.volume,
+                color: d.color.clone(),
+            })
+        })
+        .collect()
+}
+
+// 1234567 -> 1.2M
+fn format_volume(value: f64) -> String {
+    let (divisor, suffix) = [(1e9, "B"), (1e6, "M"), (1e3, "k")]
+        .into_iter()
+        .find(|&(divisor, _)| value.abs() >= divisor)
+        .unwrap_or((1.0, ""));
+    format!("{}{}", crate::utils::format_number(value / divisor), suffix)
+}
+
+impl Chart for CandlestickChart {
+    fn generate(&self, request: &GraphRequest) -> String {
+        let (theme, legend, layout) = super::frame(request);
+        let candles = candles(request);
+        let font_size = theme.tick_font_size;
+
+        let plot_height = layout.plot_bottom - layout.plot_top;
+        let max_volume = candles
+            .iter()
+            .flatten()
+            .filter_map(|c| c.volume)
+            .fold(0.0, f64::max);
+        let has_volume = candles.iter().flatten().any(|c| c.volume.is_some());
+        let (price_bottom, volume_top) = if has_volume {
+            (
+                layout.plot_bottom - plot_height * (VOLUME_SHARE + VOLUME_GAP),
+                layout.plot_bottom - plot_height * VOLUME_SHARE,
+            )
+        } else {
+            (layout.plot_bottom, layout.plot_bottom)
+        };
+
+        // Prices are usually far from zero, so the axis covers only their range
+        let (low, high) = super::min_max(
+            candles
+                .iter()
+                .flatten()
+                .flat_map(|c| [c.prices.low, c.prices.high]),
+        );
+        let y_scale = Scale::new(request.y_scale, low, high, (price_bottom, layout.plot_top));
+        let volume_scale = Scale::new(
+            ScaleType::Linear,
+            0.0,
+            max_volume,
+            (layout.plot_bottom, volume_top),
+        );
+        // Only the top of the volume scale is labelled
+        let volume_tick = volume_scale.ticks().last().copied().filter(|_| has_volume);
+
+        let layout = svg::fit_y_ticks(layout, &theme, &y_scale, request.y_label.is_some());
+        let layout = match volume_tick {
+            Some(tick) => svg::fit_left_labels(
+                layout,
+                &theme,
+                text::text_width(&format_volume(tick), font_size),
+                request.y_label.is_some(),
+            ),
+            None => layout,
+        };
+
+        let mut svg_content = svg::create_svg_header(
+            &layout,
+            &theme,
+            request.title.as_deref(),
+            request.x_label.as_deref(),
+            request.y_label.as_deref(),
+        );
+        svg_content.push_str(&svg::generate_y_axis_ticks(&layout, &theme, &y_scale));
+        // Only the first series is drawn, so it alone decides the categories
+        let mut labels = super::category_labels(request);
+        labels.truncate(candles.len());
+        svg_content.push_str(&svg::generate_x_axis_ticks_for_bar(
+            &layout, &theme, &labels,
+        ));
+
+        let segment_width = layout.plot_width / candles.len() as f64;
+        let body_width = (segment_width * 0.6).clamp(1.0, MAX_BODY_WIDTH);
+
+        if has_volume {
+            // The price panel's own baseline
+            svg_content.push_str(&format!(
+                r#"<line x1="0" y1="{price_bottom}" x2="{}" y2="{price_bottom}" stroke="{}" stroke-width="{}"/>"#,
+                layout.plot_width,
+                theme.axis,
+                theme.axis_width / 2.0,
+            ));
+            if let Some(tick) = volume_tick {
+                let y = volume_scale.map(tick);
+                svg_content.push_str(&format!(
+                    r#"<line x1="-5" y1="{y}" x2="0" y2="{y}" stroke="{}" stroke-width="{}"/><text x="-10" y="{}" text-anchor="end" font-family="M PLUS 1p" font-size="{}" fill="{}">{}</text>"#,
+                    theme.axis,
+                    theme.axis_width,
+                    y + font_size / 3.0,
+                    font_size,
+                    theme.muted,
+                    format_volume(tick)
+                ));
+            }
+        }
+
+        for (i, candle) in candles.iter().enumerate() {
+            let Some(candle) = candle else {
+                continue;
+            };
+            let Ohlc {
+                open,
+                high,
+                low,
+                close,
+            } = candle.prices;
+            let color = color::sanitize_opt(candle.color.as_ref()).unwrap_or(if close >= open {
+                &theme.up
+            } else {
+                &theme.down
+            });
+            let center = (i as f64 + 0.5) * segment_width;
+
+            if let Some(volume) = candle.volume {
+                let top = volume_scale.map(volume);
+                svg_content.push_str(&format!(
+                    r#"<rect x="{}" y="{}" width="{}" height="{}" fill="{}" fill-opacity="0.5"/>"#,
+                    center - body_width / 2.0,
+                    top,
+                    body_width,
+                    layout.plot_bottom - top,
+                    color
+                ));
+            }
+
+            let (open_y, close_y) = (y_scale.map(open), y_scale.map(close));
+            // OHLC bars are nothing but lines, so they get the full line width
+            let stroke_width = if self.is_ohlc {
+                theme.line_width
+            } else {
+                theme.line_width / 2.0
+            };
+            svg_content.push_str(&format!(
+                r#"<line x1="{center}" y1="{}" x2="{center}" y2="{}" stroke="{}" stroke-width="{}"/>"#,
+                y_scale.map(high),
+                y_scale.map(low),
+                color,
+                stroke_width,
+            ));
+            if self.is_ohlc {
+                svg_content.push_str(&format!(
+                    r#"<line x1="{}" y1="{open_y}" x2="{center}" y2="{open_y}" stroke="{color}" stroke-width="{stroke_width}"/><line x1="{center}" y1="{close_y}" x2="{}" y2="{close_y}" stroke="{color}" stroke-width="{stroke_width}"/>"#,
+                    center - body_width / 2.0,
+                    center + body_width / 2.0,
+                ));
+            } else {
+                // A flat body is still drawn as a thin line
+                svg_content.push_str(&format!(
+                    r#"<rect x="{}" y="{}" width="{}" height="{}" fill="{}"/>"#,
+                    center - body_width / 2.0,
+                    open_y.min(close_y),
+                    body_width,
+                    (open_y - close_y).abs().max(1.0),
+                    color
+                ));
+            }
+        }
+
+        svg_content.push_str(&legend.render(&layout, (layout.origin_x, layout.origin_y)));
+        svg_content.push_str(svg::create_svg_footer());
+        svg_content
+    }
+}
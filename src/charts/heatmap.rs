@@ -0,0 +1,264 @@
+use super::Chart;
+use crate::models::{GraphRequest, Matrix, PaletteName, ScaleType};
+use crate::utils::palette::{self, Kind, Palette};
+use crate::utils::{layout::Layout, scale::Scale, svg, text, theme::Theme};
+
+const RAMP_WIDTH: f64 = 16.0;
+const RAMP_GAP: f64 = 20.0;
+
+pub struct HeatmapChart {}
+
+// The request's matrix, or one built from the series: a row per series and a column
+// per point
+pub fn cells(request: &GraphRequest) -> Matrix {
+    if let Some(matrix) = &request.matrix {
+        return matrix.clone();
+    }
+    let values = if request.series.is_empty() {
+        vec![request.data.iter().copied().map(Some).collect()]
+    } else {
+        request
+            .series
+            .iter()
+            .map(|s| s.data.iter().map(|d| Some(d.value)).collect())
+            .collect()
+    };
+    Matrix {
+        rows: request
+            .series
+            .iter()
+            .enumerate()
+            .map(|(i, s)| s.name.clone().unwrap_or_else(|| (i + 1).to_string()))
+            .collect(),
+        columns: super::category_labels(request),
+        values,
+        show_values: false,
+    }
+}
+
+// Sequential or diverging ramp; categorical palettes have no order, so they fall
+// back to viridis
+pub fn ramp(request: &GraphRequest) -> Palette {
+    match request.palette.map(Palette::named) {
+        Some(palette) if palette.kind != Kind::Categorical => palette,
+        _ => Palette::named(PaletteName::Viridis),
+    }
+}
+
+// Maps values onto 0..=1 along the ramp. Diverging ramps keep their middle color at
+// zero.
+pub fn color_scale(ramp: &Palette, values: impl IntoIterator<Item = f64>) -> Scale {
+    let (min, max) = super::min_max(values);
+    let (min, max) = if ramp.kind == Kind::Diverging {
+        let extent = min.abs().max(max.abs());
+        (-extent, extent)
+    } else {
+        (min, max)
+    };
+    Scale::new(ScaleType::Linear, min, max, (0.0, 1.0))
+}
+
+// Room right of the cells for the color scale and its tick labels, at most 40% of the
+// canvas as for labels on the left
+pub fn color_scale_width(layout: &Layout, theme: &Theme, scale: &Scale) -> f64 {
+    let widest_tick = scale
+        .ticks()
+        .iter()
+        .map(|&v| text::text_width(&scale.format_tick(v), theme.tick_font_size))
+        .fold(0.0, f64::max);
+    let k = layout.margin_ratio();
+    (RAMP_GAP * k + RAMP_WIDTH + 8.0 + widest_tick + 10.0 * k).min(layout.width * 0.4)
+}
+
+// The ramp from bottom (lowest) to top with value ticks, its top-left corner at `at`
+pub fn draw_color_scale(
+    theme: &Theme,
+    ramp: &Palette,
+    scale: &Scale,
+    at: (f64, f64),
+    height: f64,
+) -> String {
+    let (x, top) = at;
+    let font_size = theme.tick_font_size;
+    let stops: String = ramp
+        .stops
+        .iter()
+        .enumerate()
+        .map(|(i, color)| {
+            format!(
+                r#"<stop offset="{}" stop-color="{}" />"#,
+                i as f64 / (ramp.stops.len() - 1) as f64,
+                color
+            )
+        })
+        .collect();
+    // Named after its stops, so that charts inlined into one page keep their own ramps
+    let id = format!("color-scale-{:016x}", fnv1a(stops.as_bytes()));
+    let mut svg = format!(
+        r#"<defs><linearGradient id="{id}" x1="0" y1="1" x2="0" y2="0">{stops}</linearGradient></defs><rect x="{}" y="{}" width="{}" height="{}" fill="url(#{id})" />"#,
+        x, top, RAMP_WIDTH, height
+    );
+    for &value in scale.ticks() {
+        let y = top + height - scale.map(value) * height;
+        svg.push_str(&format!(
+            r#"<line x1="{}" y1="{y}" x2="{}" y2="{y}" stroke="{}" stroke-width="1" /><text x="{}" y="{}" font-family="M PLUS 1p" font-size="{}">{}</text>"#,
+            x + RAMP_WIDTH,
+            x + RAMP_WIDTH + 4.0,
+            theme.axis,
+            x + RAMP_WIDTH + 8.0,
+            y + font_size / 3.0,
+            font_size,
+            scale.format_tick(value),
+        ));
+    }
+    svg
+}
+
+// 64-bit FNV-1a, stable across builds unlike the standard library's hasher
+fn fnv1a(bytes: &[u8]) -> u64 {
+    bytes.iter().fold(0xcbf2_9ce4_8422_2325, |hash, &b| {
+        (hash ^ b as u64).wrapping_mul(0x0100_0000_01b3)
+    })
+}
+
+fn labels(labels: &[String], count: usize) -> Vec<String> {
+    (0..count)
+        .map(|i| {
+            labels
+                .get(i)
+                .cloned()
+                .unwrap_or_else(|| (i + 1).to_string())
+        })
+        .collect()
+}
+
+impl Chart for HeatmapChart {
+    fn generate(&self, request: &GraphRequest) -> String {
+        let (theme, legend, layout) = super::frame(request);
+        let matrix = cells(request);
+        let row_count = matrix.values.len();
+        let column_count = matrix.values.iter().map(Vec::len).max().unwrap_or(0);
+        let rows = labels(&matrix.rows, row_count);
+        let columns = labels(&matrix.columns, column_count);
+        let font_size = theme.tick_font_size;
+
+        let ramp = ramp(request);
+        let color_scale = color_scale(&ramp, matrix.values.iter().flatten().flatten().copied());
+
+        // Row labels on the left, the color ramp with its ticks on the right
+        let max_row_label = layout.width * 0.25;
+        let rows: Vec<String> = rows
+            .iter()
+            .map(|label| text::truncate_to_width(label, max_row_label, font_size))
+            .collect();
+        let widest_row = rows
+            .iter()
+            .map(|label| text::text_width(label, font_size))
+            .fold(0.0, f64::max);
+        let layout = svg::fit_left_labels(layout, &theme, widest_row, request.y_label.is_some());
+        let layout = layout.with_right_margin(layout.right_margin().max(color_scale_width(
+            &layout,
+            &theme,
+            &color_scale,
+        )));
+
+        let mut svg_content = svg::create_svg_header_labels(
+            &layout,
+            &theme,
+            request.title.as_deref(),
+            request.x_label.as_deref(),
+            request.y_label.as_deref(),
+        );
+
+        let plot_height = layout.plot_bottom - layout.plot_top;
+        let cell_width = layout.plot_width / column_count as f64;
+        let cell_height = plot_height / row_count as f64;
+
+        for (r, row) in matrix.values.iter().enumerate() {
+            let y = layout.plot_top + r as f64 * cell_height;
+            for (c, value) in row.iter().enumerate() {
+                let Some(value) = *value else {
+                    continue;
+                };
+                let x = c as f64 * cell_width;
+                let fill = ramp.at(color_scale.map(value));
+                // Cells are separated by thin lines in the background color
+                svg_content.push_str(&format!(
+                    r#"<rect x="{}" y="{}" width="{}" height="{}" fill="{}" stroke="{}" stroke-width="1" />"#,
+                    x, y, cell_width, cell_height, fill, theme.background
+                ));
+                let label = crate::utils::format_number(value);
+                if matrix.show_values
+                    && text::text_width(&label, font_size) <= cell_width - 4.0
+                    && font_size <= cell_height
+                {
+                    svg_content.push_str(&format!(
+                        r#"<text x="{}" y="{}" text-anchor="middle" font-family="M PLUS 1p" font-size="{}" fill="{}">{}</text>"#,
+                        x + cell_width / 2.0,
+                        y + cell_height / 2.0 + font_size / 3.0,
+                        font_size,
+                        palette::text_on(&fill),
+                        label
+                    ));
+                }
+            }
+        }
+
+        // Row labels, thinned out like category ticks when rows are too short
+        let step = (font_size * 1.2 / cell_height).ceil().max(1.0) as usize;
+        for (r, label) in rows.iter().enumerate().step_by(step) {
+            svg_content.push_str(&format!(
+                r#"<text x="-10" y="{}" text-anchor="end" font-family="M PLUS 1p" font-size="{}">{}</text>"#,
+                layout.plot_top + (r as f64 + 0.5) * cell_height + font_size / 3.0,
+                font_size,
+                svg::escape_text(label)
+            ));
+        }
+        svg_content.push_str(&svg::generate_x_axis_ticks_for_bar(
+            &layout, &theme, &columns,
+        ));
+
+        svg_content.push_str(&draw_color_scale(
+            &theme,
+            &ramp,
+            &color_scale,
+            (
+                layout.plot_width + RAMP_GAP * layout.margin_ratio(),
+                layout.plot_top,
+            ),
+            plot_height,
+        ));
+
+        svg_content.push_str(&legend.render(&layout, (layout.origin_x, layout.origin_y)));
+        svg_content.push_str(svg::create_svg_footer());
+        svg_content
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn gradient_id(svg: &str) -> &str {
+        let start = svg.find(r#"<linearGradient id=""#).unwrap() + 20;
+        let end = start + svg[start..].find('"').unwrap();
+        &svg[start..end]
+    }
+
+    #[test]
+    fn gradient_ids_differ_between_ramps() {
+        let svg = |palette: &str| {
+            let request: GraphRequest = serde_json::from_value(serde_json::json!({
+                "graph_type": "heatmap",
+                "palette": palette,
+                "matrix": { "values": [[1, 2], [3, 4]] },
+            }))
+            .unwrap();
+            crate::charts::render_svg(&request)
+        };
+        let (viridis, rdbu) = (svg("viridis"), svg("rdbu"));
+        assert_ne!(gradient_id(&viridis), gradient_id(&rdbu));
+        assert_eq!(gradient_id(&viridis), gradient_id(&svg("viridis")));
+        assert!(viridis.contains(&format!("url(#{})", gradient_id(&viridis))));
+    }
+}
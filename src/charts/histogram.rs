@@ -0,0 +1,305 @@
+use super::Chart;
+use crate::models::{BinRule, Bins, GraphRequest};
+use crate::utils::{color, scale, svg};
+
+// More bins than this would be thinner than a pixel on most canvases
+pub const MAX_BINS: usize = 200;
+// Points along the density curve
+const DENSITY_SAMPLES: usize = 120;
+
+pub struct HistogramChart {}
+
+// Raw values of every series; the plain `data` array is a single series
+pub fn samples(request: &GraphRequest) -> Vec<Vec<f64>> {
+    if request.series.is_empty() {
+        vec![request.data.clone()]
+    } else {
+        request
+            .series
+            .iter()
+            .map(|s| s.data.iter().map(|d| d.value).collect())
+            .collect()
+    }
+}
+
+// Whether bins of this width, aligned to its multiples, cover the values with at most
+// MAX_BINS bins
+pub fn width_fits(values: &[f64], width: f64) -> bool {
+    let (min, max) = super::min_max(values.iter().copied());
+    min > max || max / width - (min / width).floor() <= MAX_BINS as f64
+}
+
+// Bin edges from the left edge of the first bin to the right edge of the last. Rules
+// and fixed widths line up with multiples of a (nice) width; a fixed count splits the
+// range between the smallest and largest value evenly. There are at most MAX_BINS
+// bins and the last edge always lies right of the first, even for huge values.
+pub fn bin_edges(values: &[f64], bins: Bins) -> Vec<f64> {
+    let (min, max) = super::min_max(values.iter().copied());
+    if min > max {
+        return vec![0.0, 1.0];
+    }
+    // Narrower bins would have edges that round onto each other
+    let min_width = min.abs().max(max.abs()) * f64::EPSILON * 4.0;
+    let sturges = || {
+        let k = (values.len() as f64).log2().ceil() + 1.0;
+        scale::nice_step(scale::split(min, max, k))
+    };
+    let width = match bins {
+        Bins::Count(count) => {
+            let count = count.clamp(1, MAX_BINS);
+            let (lo, hi) = if min < max {
+                (min, max)
+            } else {
+                let pad = min_width.max(0.5);
+                ((min - pad).max(f64::MIN), (max + pad).min(f64::MAX))
+            };
+            // Interpolated, as hi - lo can overflow
+            return (0..=count)
+                .map(|i| {
+                    let t = i as f64 / count as f64;
+                    lo * (1.0 - t) + hi * t
+                })
+                .collect();
+        }
+        Bins::Width { width } if width.is_finite() && width > 0.0 => width,
+        Bins::Rule(BinRule::FreedmanDiaconis) => {
+            let mut sorted = values.to_vec();
+            sorted.sort_by(f64::total_cmp);
+            let iqr = super::quantile(&sorted, 0.75) - super::quantile(&sorted, 0.25);
+            // Without a (finite) spread in the middle half the rule has nothing to go on
+            if iqr > 0.0 && iqr.is_finite() {
+                scale::nice_step(2.0 * iqr / (values.len() as f64).cbrt())
+            } else {
+                sturges()
+            }
+        }
+        _ => sturges(),
+    };
+    let width = width.max(scale::nice_step(min_width));
+    // Widths that would need too many bins give way to the nearest nice one that does not
+    let width = if width_fits(values, width) {
+        width
+    } else {
+        width.max(scale::nice_step(scale::split(
+            min,
+            max,
+            (MAX_BINS - 1) as f64,
+        )))
+    };
+    let start = ((min / width).floor() * width).max(f64::MIN);
+    let count = ((max / width - start / width).ceil() as usize).clamp(1, MAX_BINS);
+    (0..=count)
+        .map(|i| (start + i as f64 * width).min(f64::MAX))
+        .collect()
+}
+
+// Number of values in each bin. Bins include their left edge; the last one also its
+// right edge.
+pub fn counts(values: &[f64], edges: &[f64]) -> Vec<usize> {
+    let bins = edges.len() - 1;
+    let mut counts = vec![0; bins];
+    for &value in values {
+        let i = edges[1..].partition_point(|&edge| edge <= value);
+        counts[i.min(bins - 1)] += 1;
+    }
+    counts
+}
+
+// Gaussian kernel density estimate with Silverman's bandwidth, scaled to the counts
+// of bins of the given width. None when the values have no spread.
+fn density(values: &[f64], bin_width: f64, (from, to): (f64, f64)) -> Option<Vec<(f64, f64)>> {
+    let n = values.len() as f64;
+    let mean = values.iter().sum::<f64>() / n;
+    let sd = (values.iter().map(|v| (v - mean).powi(2)).sum::<f64>() / n).sqrt();
+    let mut sorted = values.to_vec();
+    sorted.sort_by(f64::total_cmp);
+    let iqr = super::quantile(&sorted, 0.75) - super::quantile(&sorted, 0.25);
+    let spread = if iqr > 0.0 { sd.min(iqr / 1.34) } else { sd };
+    let bandwidth = 0.9 * spread * n.powf(-0.2);
+    if !bandwidth.is_finite() || bandwidth <= 0.0 {
+        return None;
+    }
+    let factor = bin_width / (bandwidth * (2.0 * std::f64::consts::PI).sqrt());
+    Some(
+        (0..=DENSITY_SAMPLES)
+            .map(|i| {
+                let t = i as f64 / DENSITY_SAMPLES as f64;
+                let x = from * (1.0 - t) + to * t;
+                let sum: f64 = values
+                    .iter()
+                    .map(|v| (-0.5 * ((x - v) / bandwidth).powi(2)).exp())
+                    .sum();
+                (x, sum * factor)
+            })
+            .collect(),
+    )
+}
+
+impl Chart for HistogramChart {
+    fn generate(&self, request: &GraphRequest) -> String {
+        let (theme, legend, layout) = super::frame(request);
+        let samples = samples(request);
+        let all: Vec<f64> = samples.iter().flatten().copied().collect();
+        // All series share the bins so that they can be compared
+        let edges = bin_edges(&all, request.bins.unwrap_or_default());
+        let counts: Vec<Vec<usize>> = samples.iter().map(|v| counts(v, &edges)).collect();
+        let (lo, hi) = (edges[0], edges[edges.len() - 1]);
+        let spacing = scale::split(lo, hi, (edges.len() - 1) as f64);
+        let curves: Vec<Option<Vec<(f64, f64)>>> = samples
+            .iter()
+            .map(|values| {
+                if request.density && !values.is_empty() {
+                    density(values, spacing, (lo, hi))
+                } else {
+                    None
+                }
+            })
+            .collect();
+
+        let max_count = counts.iter().flatten().copied().max().unwrap_or(0) as f64;
+        let max_curve = curves
+            .iter()
+            .flatten()
+            .flatten()
+            .map(|&(_, y)| y)
+            .fold(0.0, f64::max);
+        let y_scale = super::value_scale(
+            request,
+            0.0,
+            max_count.max(max_curve),
+            (layout.plot_bottom, layout.plot_top),
+        );
+        let layout = svg::fit_y_ticks(layout, &theme, &y_scale, request.y_label.is_some());
+        // Stays finite for ranges near f64::MAX, like Scale::map
+        let x = |value: f64| {
+            scale::split(lo, value, 2.0) / scale::split(lo, hi, 2.0) * layout.plot_width
+        };
+
+        let mut svg_content = svg::create_svg_header(
+            &layout,
+            &theme,
+            request.title.as_deref(),
+            request.x_label.as_deref(),
+            request.y_label.as_deref(),
+        );
+        svg_content.push_str(&svg::generate_y_axis_ticks(&layout, &theme, &y_scale));
+
+        // Bars of the same bin touch; several series overlap translucently
+        let opacity = if samples.len() > 1 { 0.6 } else { 1.0 };
+        for (series_idx, counts) in counts.iter().enumerate() {
+            let color = color::resolve(&theme, request, series_idx, None);
+            for (i, &count) in counts.iter().enumerate() {
+                if count == 0 {
+                    continue;
+                }
+                let top = y_scale.map(count as f64);
+                svg_content.push_str(&format!(
+                    r#"<rect x="{}" y="{}" width="{}" height="{}" fill="{}" fill-opacity="{}" stroke="{}" stroke-width="1"/>"#,
+                    x(edges[i]),
+                    top,
+                    x(edges[i + 1]) - x(edges[i]),
+                    y_scale.baseline() - top,
+                    color,
+                    opacity,
+                    theme.background
+                ));
+            }
+        }
+
+        for (series_idx, curve) in curves.iter().enumerate() {
+            let Some(curve) = curve else {
+                continue;
+            };
+            let path: Vec<String> = curve
+                .iter()
+                // Log axes have no zero; the tails stay on the floor
+                .map(|&(v, y)| format!("{:.1} {:.1}", x(v), y_scale.map(y).min(y_scale.baseline())))
+                .collect();
+            // One curve stands out in the text color; several keep their series colors
+            let stroke = if samples.len() > 1 {
+                color::resolve(&theme, request, series_idx, None)
+            } else {
+                theme.foreground.as_str()
+            };
+            svg_content.push_str(&format!(
+                r#"<path d="M {}" stroke="{}" stroke-width="{}" fill="none" />"#,
+                path.join(" L "),
+                stroke,
+                theme.line_width
+            ));
+        }
+
+        let positions: Vec<f64> = edges.iter().map(|&edge| x(edge)).collect();
+        let labels: Vec<String> = edges
+            .iter()
+            .map(|&edge| scale::format_on_grid(edge, spacing))
+            .collect();
+        svg_content.push_str(&svg::generate_x_axis_ticks_at(
+            &layout, &theme, &positions, &labels,
+        ));
+
+        svg_content.push_str(&legend.render(&layout, (layout.origin_x, layout.origin_y)));
+        svg_content.push_str(svg::create_svg_footer());
+        svg_content
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn bins_line_up_with_nice_widths() {
+        let values = [0.3, 1.2, 2.5, 2.7, 4.9, 9.6];
+        // Sturges: 4 bins for 6 values, 9.3 / 4 rounded up to 2.5
+        let edges = bin_edges(&values, Bins::Rule(BinRule::Sturges));
+        assert_eq!(edges, [0.0, 2.5, 5.0, 7.5, 10.0]);
+        assert_eq!(counts(&values, &edges), [2, 3, 0, 1]);
+        assert_eq!(
+            bin_edges(&values, Bins::width(2.0)),
+            [0.0, 2.0, 4.0, 6.0, 8.0, 10.0]
+        );
+        let edges = bin_edges(&values, Bins::Count(3));
+        assert_eq!(edges.len(), 4);
+        assert_eq!((edges[0], edges[3]), (0.3, 9.6));
+        // The largest value falls into the last bin, not past it
+        assert_eq!(counts(&values, &edges), [4, 1, 1]);
+    }
+
+    #[test]
+    fn narrow_widths_are_only_widened_past_the_bin_limit() {
+        let values = [0.0, 1030.0];
+        let edges = bin_edges(&values, Bins::width(5.2));
+        assert_eq!(edges.len(), 200);
+        assert_eq!(edges[1], 5.2);
+        assert!(!width_fits(&values, 5.0));
+        let edges = bin_edges(&values, Bins::width(5.0));
+        assert!(edges.len() <= MAX_BINS + 1);
+        assert!(edges[0] <= 0.0 && edges[edges.len() - 1] >= 1030.0);
+    }
+
+    #[test]
+    fn bins_stay_few_and_wide_for_extreme_values() {
+        for bins in [
+            Bins::Rule(BinRule::Sturges),
+            Bins::Rule(BinRule::FreedmanDiaconis),
+            Bins::width(1e-3),
+            Bins::Count(1),
+            Bins::Count(50),
+        ] {
+            for values in [
+                &[-1e308, 1e308][..],
+                &[f64::MIN, f64::MAX],
+                &[1e17, 1e17],
+                &[1e308],
+            ] {
+                let edges = bin_edges(values, bins);
+                let (lo, hi) = (edges[0], edges[edges.len() - 1]);
+                assert!(edges.len() <= MAX_BINS + 1, "{values:?} {bins:?}");
+                assert!(edges.iter().all(|e| e.is_finite()), "{values:?} {bins:?}");
+                assert!(lo < hi, "{values:?} {bins:?}: {edges:?}");
+                assert!(lo <= values[0] && hi >= values[values.len() - 1]);
+            }
+        }
+    }
+}
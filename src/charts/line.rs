@@ -1,19 +1,14 @@
 use super::Chart;
 use crate::models::GraphRequest;
-use crate::utils::{self, svg};
+use crate::utils::{self, color, svg};
 
 pub struct LineChart {}
 
 impl Chart for LineChart {
     fn generate(&self, request: &GraphRequest) -> String {
-        let mut svg_content = svg::create_svg_header(
-            request.title.as_deref(),
-            request.x_label.as_deref(),
-            request.y_label.as_deref(),
-        );
-
+        let (theme, legend, layout) = super::frame(request);
         let series = if request.series.is_empty() {
-            vec![request.data.iter().map(|&v| v).collect::<Vec<f64>>()]
+            vec![request.data.to_vec()]
         } else {
             request
                 .series
@@ -22,28 +17,38 @@ impl Chart for LineChart {
                 .collect()
         };
 
-        let raw_max = super::get_max_value(&request.series);
-        let max_value = svg::nice_max(raw_max);
-        let segment_width = 640.0 / (series[0].len() as f64 - 1.0);
+        let (min_value, max_value) = super::get_value_range(&series);
+        let y_scale = super::value_scale(
+            request,
+            min_value,
+            max_value,
+            (layout.plot_bottom, layout.plot_top),
+        );
+        let layout = svg::fit_y_ticks(layout, &theme, &y_scale, request.y_label.is_some());
+        let x_axis = super::XAxis::new(request, &layout);
 
-        svg_content.push_str(&utils::svg::generate_y_axis_ticks(max_value));
-        svg_content.push_str(&utils::svg::generate_x_axis_ticks_for_line(series[0].len()));
+        let mut svg_content = svg::create_svg_header(
+            &layout,
+            &theme,
+            request.title.as_deref(),
+            request.x_label.as_deref(),
+            request.y_label.as_deref(),
+        );
+
+        svg_content.push_str(&utils::svg::generate_y_axis_ticks(
+            &layout, &theme, &y_scale,
+        ));
+        svg_content.push_str(&utils::svg::generate_zero_line(&layout, &theme, &y_scale));
+        svg_content.push_str(&x_axis.ticks(request, &layout, &theme));
 
         for (series_idx, series_data) in series.iter().enumerate() {
-            let color = if request.series.is_empty() {
-                utils::get_default_colors()[0]
-            } else {
-                request.series[series_idx]
-                    .color
-                    .as_deref()
-                    .unwrap_or_else(|| utils::get_default_colors()[series_idx % utils::get_default_colors().len()])
-            };
+            let color = color::resolve(&theme, request, series_idx, None);
 
             // Draw line
             let mut path = String::new();
             for (i, &value) in series_data.iter().enumerate() {
-                let x = i as f64 * segment_width;
-                let y = 450.0 - ((value / max_value) * (450.0 - 50.0));
+                let x = x_axis.position(request, &layout, series_idx, i);
+                let y = y_scale.map(value);
                 if i == 0 {
                     path.push_str(&format!("M {:.1} {:.1}", x, y));
                 } else {
@@ -51,26 +56,29 @@ impl Chart for LineChart {
                 }
             }
             svg_content.push_str(&format!(
-                r#"<path d="{}" stroke="{}" stroke-width="2" fill="none" />"#,
-                path, color
+                r#"<path d="{}" stroke="{}" stroke-width="{}" fill="none" />"#,
+                path, color, theme.line_width
             ));
 
             // Draw points and values
             for (i, &value) in series_data.iter().enumerate() {
-                let x = i as f64 * segment_width;
-                let y = 450.0 - ((value / max_value) * (450.0 - 50.0));
+                let x = x_axis.position(request, &layout, series_idx, i);
+                let y = y_scale.map(value);
                 svg_content.push_str(&format!(
                     r#"<circle cx="{}" cy="{}" r="4" fill="{}" />"#,
                     x, y, color
                 ));
-                svg_content.push_str(&utils::svg::generate_value_text(x, y, value));
+                svg_content.push_str(&svg::generate_value_text(
+                    &theme,
+                    x,
+                    y,
+                    value,
+                    &y_scale.format_value(value),
+                ));
             }
         }
 
-        // Legend: only render entries that have names
-        if !request.series.is_empty() {
-            svg_content.push_str(&svg::create_legend(&request.series, 660.0, 50.0));
-        }
+        svg_content.push_str(&legend.render(&layout, (layout.origin_x, layout.origin_y)));
 
         svg_content.push_str(svg::create_svg_footer());
         svg_content
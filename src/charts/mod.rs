@@ -1,11 +1,17 @@
 pub mod area;
 pub mod bar;
+pub mod boxplot;
+pub mod calendar;
+pub mod candlestick;
+pub mod heatmap;
+pub mod histogram;
 pub mod line;
 pub mod pie;
 pub mod radar;
 pub mod scatter;
 
-use crate::models::{GraphRequest, GraphType, Series};
+use crate::models::{GraphRequest, GraphType, ScaleType, Series, Stacking};
+use crate::utils::{layout::Layout, legend::Legend, scale::Scale, svg, text, theme::Theme};
 
 pub trait Chart {
     fn generate(&self, request: &GraphRequest) -> String;
@@ -15,14 +21,139 @@ pub fn create_chart(request: &GraphRequest) -> Box<dyn Chart> {
     match request.graph_type {
         GraphType::Bar => Box::new(bar::BarChart {}),
         GraphType::Line => Box::new(line::LineChart {}),
-        GraphType::Scatter => Box::new(scatter::ScatterChart {}),
+        GraphType::Scatter => Box::new(scatter::ScatterChart { is_bubble: false }),
+        GraphType::Bubble => Box::new(scatter::ScatterChart { is_bubble: true }),
         GraphType::Pie => Box::new(pie::PieChart { is_donut: false }),
         GraphType::Donut => Box::new(pie::PieChart { is_donut: true }),
         GraphType::Area => Box::new(area::AreaChart {}),
         GraphType::Radar => Box::new(radar::RadarChart {}),
+        GraphType::Heatmap => Box::new(heatmap::HeatmapChart {}),
+        GraphType::Calendar => Box::new(calendar::CalendarChart {}),
+        GraphType::Histogram => Box::new(histogram::HistogramChart {}),
+        GraphType::Box => Box::new(boxplot::BoxPlotChart {}),
+        GraphType::Candlestick => Box::new(candlestick::CandlestickChart { is_ohlc: false }),
+        GraphType::Ohlc => Box::new(candlestick::CandlestickChart { is_ohlc: true }),
     }
 }
 
+// Entry point for drawing: every request is normalized first so that the charts
+// only ever see finite values, and a placeholder is drawn when nothing is left to plot.
+pub fn render_svg(request: &GraphRequest) -> String {
+    let request = normalize(request);
+    if !has_data(&request) {
+        let layout = Layout::from_request(&request);
+        let theme = Theme::from_request(&request);
+        return svg::create_placeholder(&layout, &theme, request.title.as_deref(), "No data");
+    }
+    create_chart(&request).generate(&request)
+}
+
+// Theme, legend and the layout that is left for the chart once the legend has its space
+pub fn frame(request: &GraphRequest) -> (Theme, Legend, Layout) {
+    let theme = Theme::from_request(request);
+    let mut layout = Layout::from_request(request);
+    if let Some(title) = &request.title {
+        let lines = svg::title_lines(&layout, &theme, title).len();
+        layout = layout.with_title_height(
+            lines.saturating_sub(1) as f64 * text::line_height(theme.title_font_size),
+        );
+    }
+    let legend = Legend::new(request, &theme, &layout);
+    let layout = legend.reserve(layout);
+    (theme, legend, layout)
+}
+
+// Drop non-finite values and optional coordinates, and series left with nothing to draw.
+// Volumes below zero only get through lenient parsing and the builder; they draw as none.
+fn normalize(request: &GraphRequest) -> GraphRequest {
+    let mut request = request.clone();
+    request.data.retain(|v| v.is_finite());
+    for series in &mut request.series {
+        series.data.retain(|d| d.value.is_finite());
+        for point in &mut series.data {
+            point.x = point.x.filter(|x| x.is_finite());
+            point.size = point.size.filter(|s| s.is_finite());
+            point.volume = point.volume.filter(|v| v.is_finite()).map(|v| v.max(0.0));
+            point.ohlc = point.ohlc.filter(|p| {
+                [p.open, p.high, p.low, p.close]
+                    .iter()
+                    .all(|v| v.is_finite())
+            });
+        }
+        series.summary = series.summary.filter(|s| {
+            [s.min, s.q1, s.median, s.q3, s.max]
+                .iter()
+                .chain(&s.mean)
+                .all(|v| v.is_finite())
+        });
+    }
+    request
+        .series
+        .retain(|s| !s.data.is_empty() || s.summary.is_some());
+    if let Some(matrix) = &mut request.matrix {
+        for cell in matrix.values.iter_mut().flatten() {
+            *cell = cell.filter(|v| v.is_finite());
+        }
+    }
+    request
+}
+
+fn has_data(request: &GraphRequest) -> bool {
+    match request.graph_type {
+        GraphType::Heatmap => {
+            return heatmap::cells(request)
+                .values
+                .iter()
+                .flatten()
+                .any(Option::is_some)
+        }
+        // Points without a date cannot be placed
+        GraphType::Calendar => return !calendar::days(request).is_empty(),
+        // A series may bring a summary instead of values
+        GraphType::Box => return boxplot::boxes(request).iter().any(Option::is_some),
+        // Only points with prices can be drawn
+        GraphType::Candlestick | GraphType::Ohlc => {
+            return candlestick::candles(request).iter().any(Option::is_some)
+        }
+        _ => {}
+    }
+    let mut values = request.data.iter().copied().chain(
+        request
+            .series
+            .iter()
+            .flat_map(|s| s.data.iter().map(|d| d.value)),
+    );
+    match request.graph_type {
+        // Slices need a positive total
+        GraphType::Pie | GraphType::Donut => values.any(|v| v > 0.0),
+        _ => values.next().is_some(),
+    }
+}
+
+// Smallest and largest of the given values
+pub fn min_max(values: impl IntoIterator<Item = f64>) -> (f64, f64) {
+    values
+        .into_iter()
+        .fold((f64::INFINITY, f64::NEG_INFINITY), |(lo, hi), v| {
+            (lo.min(v), hi.max(v))
+        })
+}
+
+// Value below which the share p (0 to 1) of the sorted, non-empty values lies,
+// interpolated between neighbours without taking their (possibly overflowing) difference
+pub fn quantile(sorted: &[f64], p: f64) -> f64 {
+    let position = (sorted.len() - 1) as f64 * p.clamp(0.0, 1.0);
+    let below = position.floor() as usize;
+    let above = position.ceil() as usize;
+    let t = position - below as f64;
+    sorted[below] * (1.0 - t) + sorted[above] * t
+}
+
+// Smallest and largest value across all series
+pub fn get_value_range(series: &[Vec<f64>]) -> (f64, f64) {
+    min_max(series.iter().flatten().copied())
+}
+
 pub fn get_max_value(series: &[Series]) -> f64 {
     series
         .iter()
@@ -30,3 +161,414 @@ pub fn get_max_value(series: &[Series]) -> f64 {
         .map(|d| d.value)
         .fold(f64::NEG_INFINITY, f64::max)
 }
+
+// Number of category slots: the length of the longest series
+pub fn category_count(request: &GraphRequest) -> usize {
+    if request.series.is_empty() {
+        request.data.len()
+    } else {
+        request
+            .series
+            .iter()
+            .map(|s| s.data.len())
+            .max()
+            .unwrap_or(0)
+    }
+}
+
+// Category names for the x-axis, taken from the first series with a point label in
+// each slot and falling back to 1-based indices where none has one
+pub fn category_labels(request: &GraphRequest) -> Vec<String> {
+    (0..category_count(request))
+        .map(|i| {
+            request
+                .series
+                .iter()
+                .find_map(|s| s.data.get(i)?.label.clone())
+                .unwrap_or_else(|| (i + 1).to_string())
+        })
+        .collect()
+}
+
+// Horizontal axis of line and area charts: evenly spaced categories, or a scale over
+// the points' x values once any point has one. As on scatter charts, points without
+// an x then sit at their 1-based index.
+pub enum XAxis {
+    Categories(usize),
+    Values(Scale),
+}
+
+impl XAxis {
+    // Built for the layout the chart is finally drawn in
+    pub fn new(request: &GraphRequest, layout: &Layout) -> Self {
+        let points = || {
+            request
+                .series
+                .iter()
+                .flat_map(|s| s.data.iter().enumerate())
+        };
+        if !points().any(|(_, d)| d.x.is_some()) {
+            return XAxis::Categories(category_count(request));
+        }
+        let (min, max) = min_max(points().map(|(i, d)| d.x.unwrap_or((i + 1) as f64)));
+        XAxis::Values(Scale::new(
+            request.x_scale,
+            min,
+            max,
+            (0.0, layout.plot_width),
+        ))
+    }
+
+    // x of the i-th point of a series
+    pub fn position(
+        &self,
+        request: &GraphRequest,
+        layout: &Layout,
+        series_idx: usize,
+        i: usize,
+    ) -> f64 {
+        match self {
+            XAxis::Categories(count) => svg::line_point_x(layout, i, *count),
+            XAxis::Values(scale) => scale.map(
+                request
+                    .series
+                    .get(series_idx)
+                    .and_then(|s| s.data.get(i))
+                    .and_then(|d| d.x)
+                    .unwrap_or((i + 1) as f64),
+            ),
+        }
+    }
+
+    pub fn ticks(&self, request: &GraphRequest, layout: &Layout, theme: &Theme) -> String {
+        match self {
+            XAxis::Categories(_) => {
+                svg::generate_x_axis_ticks_for_line(layout, theme, &category_labels(request))
+            }
+            XAxis::Values(scale) => svg::generate_x_axis_ticks_for_scale(layout, theme, scale),
+        }
+    }
+}
+
+// Vertical value scale shared by the cartesian charts. Linear axes always include
+// zero so bars and areas have a baseline to grow from.
+pub fn value_scale(request: &GraphRequest, min: f64, max: f64, range: (f64, f64)) -> Scale {
+    let (min, max) = match request.y_scale {
+        ScaleType::Linear => (min.min(0.0), max.max(0.0)),
+        _ => (min, max),
+    };
+    Scale::new(request.y_scale, min, max, range)
+}
+
+// (start, end) of every point's segment along the value axis. Without stacking each
+// segment starts at zero; stacked segments accumulate per category, with positive and
+// negative values growing away from zero separately. Percent stacking normalizes each
+// category to 100.
+pub fn stack_values(series: &[Vec<f64>], stacking: Stacking) -> Vec<Vec<(f64, f64)>> {
+    if stacking == Stacking::None {
+        return series
+            .iter()
+            .map(|values| values.iter().map(|&v| (0.0, v)).collect())
+            .collect();
+    }
+
+    let categories = series.iter().map(Vec::len).max().unwrap_or(0);
+    let totals: Vec<f64> = (0..categories)
+        .map(|i| {
+            series
+                .iter()
+                .filter_map(|values| values.get(i))
+                .map(|v| v.abs())
+                .sum()
+        })
+        .collect();
+    let mut positive = vec![0.0; categories];
+    let mut negative = vec![0.0; categories];
+
+    series
+        .iter()
+        .map(|values| {
+            values
+                .iter()
+                .enumerate()
+                .map(|(i, &v)| {
+                    let v = match stacking {
+                        Stacking::Percent if totals[i] > 0.0 => v / totals[i] * 100.0,
+                        Stacking::Percent => 0.0,
+                        _ => v,
+                    };
+                    let acc = if v < 0.0 {
+                        &mut negative[i]
+                    } else {
+                        &mut positive[i]
+                    };
+                    let start = *acc;
+                    *acc += v;
+                    (start, *acc)
+                })
+                .collect()
+        })
+        .collect()
+}
+
+// Value label for a stacked segment: the original value, or its share for percent stacks
+pub fn stacked_value_text(value: f64, start: f64, end: f64, stacking: Stacking) -> String {
+    match stacking {
+        Stacking::Percent => format!("{:.1}%", end - start),
+        _ => crate::utils::format_number(value),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use resvg::usvg::{self, TreeParsing};
+
+    const HOSTILE_TEXT: &[&str] = &[
+        "Profit & Loss",
+        "<b>bold</b>",
+        r#"say "hi" & 'bye'"#,
+        "</text><script>alert(1)</script><text>",
+        "]]><!-- --><![CDATA[",
+        "&amp; &#x3C; &unknown;",
+        "nul\u{0}bell\u{7}escape\u{1b}",
+        "東京 & 大阪 <支店>",
+    ];
+
+    const HOSTILE_COLORS: &[&str] = &[
+        r#"red" onload="alert(1)"#,
+        r##"#000"/><script>alert(1)</script><rect fill="#000"##,
+        "url(javascript:alert(1))",
+        "rgb(0,0,0);stroke:red",
+    ];
+
+    fn hostile_request(graph_type: &str, text: &str, color: &str) -> GraphRequest {
+        let point =
+            |value: f64| serde_json::json!({ "value": value, "label": text, "color": color });
+        serde_json::from_value(serde_json::json!({
+            "graph_type": graph_type,
+            "title": text,
+            "x_label": text,
+            "y_label": text,
+            "colors": [color, color],
+            "theme": {
+                "background": color,
+                "foreground": color,
+                "muted": color,
+                "grid": color,
+                "axis": color,
+                "palette": [color],
+            },
+            "series": [
+                { "name": text, "color": color, "data": [point(1.0), point(3.0), point(2.0)] },
+                { "name": text, "color": color, "data": [point(2.0), point(1.0), point(4.0)] },
+            ],
+        }))
+        .unwrap()
+    }
+
+    fn assert_renders(request: &GraphRequest, context: &str) {
+        let svg = render_svg(request);
+        if let Err(e) = usvg::Tree::from_str(&svg, &usvg::Options::default()) {
+            panic!("{context}: invalid SVG ({e})\n{svg}");
+        }
+        assert!(
+            !svg.contains("<script"),
+            "{context}: injected element\n{svg}"
+        );
+        assert!(
+            !svg.contains("onload="),
+            "{context}: injected attribute\n{svg}"
+        );
+    }
+
+    #[test]
+    fn hostile_text_and_colors_render_safely() {
+        for graph_type in ALL_TYPES {
+            for text in HOSTILE_TEXT {
+                for color in HOSTILE_COLORS {
+                    let request = hostile_request(graph_type, text, color);
+                    assert_renders(&request, &format!("{graph_type} {text:?} {color:?}"));
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn hostile_category_labels_on_horizontal_bars() {
+        for text in HOSTILE_TEXT {
+            let mut request = hostile_request("bar", text, "#4E79A7");
+            request.orientation = crate::models::Orientation::Horizontal;
+            assert_renders(&request, &format!("horizontal bar {text:?}"));
+        }
+    }
+
+    const ALL_TYPES: &[&str] = &[
+        "line",
+        "bar",
+        "scatter",
+        "pie",
+        "donut",
+        "area",
+        "radar",
+        "bubble",
+        "heatmap",
+        "calendar",
+        "histogram",
+        "box",
+        "candlestick",
+        "ohlc",
+    ];
+
+    fn degenerate_requests(graph_type: &str) -> Vec<(&'static str, GraphRequest)> {
+        use serde_json::json;
+        let cases = vec![
+            ("no data", json!({ "graph_type": graph_type, "data": [] })),
+            (
+                "empty series",
+                json!({ "graph_type": graph_type, "series": [{ "name": "a", "data": [] }] }),
+            ),
+            (
+                "single value",
+                json!({ "graph_type": graph_type, "data": [5] }),
+            ),
+            (
+                "single point",
+                json!({ "graph_type": graph_type, "series": [{ "data": [[2, 3, 4]] }] }),
+            ),
+            (
+                "all zero",
+                json!({ "graph_type": graph_type, "data": [0, 0, 0] }),
+            ),
+            (
+                "all zero series",
+                json!({ "graph_type": graph_type, "series": [
+                { "name": "a", "data": [0, 0] }, { "name": "b", "data": [0, 0] }
+            ] }),
+            ),
+            (
+                "ragged series",
+                json!({ "graph_type": graph_type, "series": [
+                { "name": "a", "data": [1] }, { "name": "b", "data": [] }, { "name": "c", "data": [3, 4, 5] }
+            ] }),
+            ),
+            (
+                "empty first series",
+                json!({ "graph_type": graph_type, "series": [
+                { "name": "a", "data": [] }, { "name": "b", "data": [1, 2] }
+            ] }),
+            ),
+            (
+                "far timestamps",
+                json!({ "graph_type": graph_type, "x_scale": "time", "series": [{ "data": [
+                { "x": 1e300, "value": 1 }, { "x": -1e300, "value": 2 }, { "x": 0, "value": 3 }
+            ] }] }),
+            ),
+            (
+                "largest values",
+                json!({ "graph_type": graph_type, "show_mean": true, "density": true, "data": [-1e308, 1e308] }),
+            ),
+            (
+                "overflowing sum",
+                json!({ "graph_type": graph_type, "show_mean": true, "density": true, "data": [1e308, 1e308, -1e308] }),
+            ),
+            (
+                "identical huge values",
+                json!({ "graph_type": graph_type, "density": true, "data": [1e17, 1e17] }),
+            ),
+            (
+                "zero sizes",
+                json!({ "graph_type": graph_type, "series": [{ "data": [[1, 1, 0], [2, 2, 0]] }] }),
+            ),
+            (
+                "stacked zero",
+                json!({ "graph_type": graph_type, "stacking": "percent", "data": [0, 0] }),
+            ),
+            (
+                "horizontal single",
+                json!({ "graph_type": graph_type, "orientation": "horizontal", "data": [1] }),
+            ),
+            (
+                "log zero",
+                json!({ "graph_type": graph_type, "y_scale": "log", "data": [0, 0] }),
+            ),
+            (
+                "log subnormal",
+                json!({ "graph_type": graph_type, "y_scale": "log", "data": [5e-324, 10] }),
+            ),
+            (
+                "time far apart",
+                json!({ "graph_type": graph_type, "y_scale": "time", "data": [-1e300, 0, 1e300] }),
+            ),
+            (
+                "time single",
+                json!({ "graph_type": graph_type, "y_scale": "time", "x_scale": "time", "data": [0] }),
+            ),
+        ];
+        let mut requests: Vec<(&str, GraphRequest)> = cases
+            .into_iter()
+            .map(|(case, json)| (case, serde_json::from_value(json).unwrap()))
+            .collect();
+
+        // Parsing rejects negative volumes, the builder does not
+        let mut negative_volume: GraphRequest =
+            serde_json::from_value(json!({ "graph_type": graph_type, "series": [{ "data": [] }] }))
+                .unwrap();
+        negative_volume.series[0].data = vec![
+            crate::models::DataPoint::ohlc(1.0, 3.0, 0.0, 2.0).volume(-5.0),
+            crate::models::DataPoint::ohlc(2.0, 4.0, 1.0, 3.0).volume(-1.0),
+        ];
+        requests.push(("negative volume", negative_volume));
+        requests
+    }
+
+    // Bounding box of the first shape that reaches past the canvas edges
+    fn outside_canvas(tree: &usvg::Tree) -> Option<usvg::Rect> {
+        use usvg::NodeExt;
+        let (width, height) = (tree.size.width(), tree.size.height());
+        tree.root.descendants().find_map(|node| {
+            let bbox = node.calculate_bbox()?;
+            let inside = bbox.left() >= -1.0
+                && bbox.top() >= -1.0
+                && bbox.right() <= width + 1.0
+                && bbox.bottom() <= height + 1.0;
+            (!inside).then_some(bbox)
+        })
+    }
+
+    #[test]
+    fn degenerate_input_renders_valid_svg() {
+        let mut failures = Vec::new();
+        for graph_type in ALL_TYPES {
+            for (case, request) in degenerate_requests(graph_type) {
+                let context = format!("{graph_type} / {case}");
+                let svg = match std::panic::catch_unwind(|| render_svg(&request)) {
+                    Ok(svg) => svg,
+                    Err(_) => {
+                        failures.push(format!("{context}: panicked"));
+                        continue;
+                    }
+                };
+                if svg.contains("NaN") || svg.contains("inf") {
+                    failures.push(format!("{context}: non-finite coordinate"));
+                    continue;
+                }
+                // usvg skips such shapes instead of failing on them
+                if svg.contains(r#"width="-"#) || svg.contains(r#"height="-"#) {
+                    failures.push(format!("{context}: negative size"));
+                    continue;
+                }
+                match usvg::Tree::from_str(&svg, &usvg::Options::default()) {
+                    Ok(tree) => {
+                        if let Some(bbox) = outside_canvas(&tree) {
+                            failures
+                                .push(format!("{context}: shape outside the canvas ({bbox:?})"));
+                        }
+                    }
+                    Err(e) => failures.push(format!("{context}: invalid SVG ({e})")),
+                }
+            }
+        }
+        assert!(failures.is_empty(), "{}", failures.join("\n"));
+    }
+}
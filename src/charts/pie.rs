@@ -1,6 +1,6 @@
 use super::Chart;
 use crate::models::GraphRequest;
-use crate::utils;
+use crate::utils::{color, svg};
 use std::f64::consts::PI;
 
 pub struct PieChart {
@@ -9,58 +9,64 @@ pub struct PieChart {
 
 impl Chart for PieChart {
     fn generate(&self, request: &GraphRequest) -> String {
-        let mut svg_content = String::from(
-            r#"<?xml version="1.0" encoding="UTF-8" standalone="no"?>
-<svg width="800" height="600" xmlns="http://www.w3.org/2000/svg">
-<rect width="800" height="600" fill="white"/>
-<g transform="translate(400, 300)">"#,
-        );
+        let (theme, legend, layout) = super::frame(request);
+        // Slices are drawn around the center of the space the legend leaves
+        let (center_x, center_y) = layout.free_center();
+        let mut svg_content = svg::create_svg_root(&layout, &theme, (center_x, center_y));
 
         if let Some(title) = &request.title {
-            svg_content.push_str(&format!(
-                r#"<text x="0" y="-250" text-anchor="middle" font-family="M PLUS 1p" font-size="20">{}</text>"#,
-                title
+            svg_content.push_str(&svg::title(
+                &layout,
+                &theme,
+                title,
+                (layout.width / 2.0, layout.origin_y),
+                (center_x, center_y),
             ));
         }
 
+        // Negative values have no meaningful slice and count as zero
+        let share = |value: f64, total: f64| {
+            if total > 0.0 {
+                value.max(0.0) / total * 100.0
+            } else {
+                0.0
+            }
+        };
         let series = if request.series.is_empty() {
-            let total: f64 = request.data.iter().sum();
-            let colors = request.colors.clone().unwrap_or_default();
+            let total: f64 = request.data.iter().map(|v| v.max(0.0)).sum();
             vec![request
                 .data
                 .iter()
-                .enumerate()
-                .map(|(i, &value)| {
-                    let color = colors.get(i).cloned();
-                    (value / total * 100.0, color)
-                })
+                .map(|&value| share(value, total))
                 .collect::<Vec<_>>()]
         } else {
             request
                 .series
                 .iter()
                 .map(|s| {
-                    let total: f64 = s.data.iter().map(|d| d.value).sum();
+                    let total: f64 = s.data.iter().map(|d| d.value.max(0.0)).sum();
                     s.data
                         .iter()
-                        .map(|d| (d.value / total * 100.0, d.color.clone()))
+                        .map(|d| share(d.value, total))
                         .collect::<Vec<_>>()
                 })
                 .collect()
         };
 
-        let radius = 180.0;
+        let radius = (layout.width.min(layout.height) * 0.3)
+            .min(layout.free_radius(20.0 * layout.margin_ratio()));
         let inner_radius = if self.is_donut { radius * 0.6 } else { 0.0 };
         let mut current_angle = -90.0; // Start from top
 
-        for series_data in series {
-            for (i, (percentage, color_opt)) in series_data.iter().enumerate() {
+        for (series_idx, series_data) in series.iter().enumerate() {
+            for (i, percentage) in series_data.iter().enumerate() {
+                if *percentage <= 0.0 {
+                    continue;
+                }
                 let angle = 360.0 * percentage / 100.0;
                 let end_angle = current_angle + angle;
 
-                let color = color_opt.as_ref().map(String::as_str).unwrap_or_else(|| {
-                    utils::get_default_colors()[i % utils::get_default_colors().len()]
-                });
+                let color = color::resolve(&theme, request, series_idx, Some(i));
 
                 // Calculate arc points
                 let start_rad = current_angle * PI / 180.0;
@@ -73,7 +79,22 @@ impl Chart for PieChart {
 
                 let large_arc = if angle > 180.0 { 1 } else { 0 };
 
-                if self.is_donut {
+                if angle >= 359.99 {
+                    // A single slice covers the whole circle, which one arc cannot draw
+                    if self.is_donut {
+                        svg_content.push_str(&format!(
+                            r#"<circle cx="0" cy="0" r="{:.1}" fill="none" stroke="{}" stroke-width="{:.1}" />"#,
+                            (radius + inner_radius) / 2.0,
+                            color,
+                            radius - inner_radius
+                        ));
+                    } else {
+                        svg_content.push_str(&format!(
+                            r#"<circle cx="0" cy="0" r="{:.1}" fill="{}" />"#,
+                            radius, color
+                        ));
+                    }
+                } else if self.is_donut {
                     let inner_start_x = inner_radius * start_rad.cos();
                     let inner_start_y = inner_radius * start_rad.sin();
                     let inner_end_x = inner_radius * end_rad.cos();
@@ -100,15 +121,16 @@ impl Chart for PieChart {
                 let label_y = label_radius * label_angle.sin();
 
                 svg_content.push_str(&format!(
-                    r#"<text x="{:.1}" y="{:.1}" text-anchor="middle" font-family="M PLUS 1p" font-size="12">{:.1}%</text>"#,
-                    label_x, label_y + 4.0, percentage
+                    r#"<text x="{:.1}" y="{:.1}" text-anchor="middle" font-family="M PLUS 1p" font-size="{}">{:.1}%</text>"#,
+                    label_x, label_y + 4.0, theme.tick_font_size, percentage
                 ));
 
                 current_angle = end_angle;
             }
         }
 
-        svg_content.push_str("</g></svg>");
+        svg_content.push_str(&legend.render(&layout, (center_x, center_y)));
+        svg_content.push_str(svg::create_svg_footer());
         svg_content
     }
 }
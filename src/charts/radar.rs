@@ -1,22 +1,23 @@
 use super::Chart;
 use crate::models::GraphRequest;
-use crate::utils::{self, svg};
+use crate::utils::{color, svg};
 use std::f64::consts::PI;
 
 pub struct RadarChart {}
 
 impl Chart for RadarChart {
     fn generate(&self, request: &GraphRequest) -> String {
-        let mut svg_content = svg::create_svg_header_no_axes(
-            request.title.as_deref(),
-            None,
-            None,
-        );
+        let (theme, legend, layout) = super::frame(request);
+        let mut svg_content =
+            svg::create_svg_header_no_axes(&layout, &theme, request.title.as_deref());
 
         // データとラベルを保持する構造を生成
         let (series, axis_labels) = if request.series.is_empty() {
-            let series_data = vec![request.data.iter().map(|&v| v).collect::<Vec<f64>>()];
-            let labels: Vec<String> = request.data.iter().enumerate()
+            let series_data = vec![request.data.to_vec()];
+            let labels: Vec<String> = request
+                .data
+                .iter()
+                .enumerate()
                 .map(|(i, _)| format!("Axis {}", i + 1))
                 .collect();
             (series_data, labels)
@@ -26,13 +27,13 @@ impl Chart for RadarChart {
                 .iter()
                 .map(|d| d.label.as_ref().unwrap_or(&"".to_string()).to_string())
                 .collect();
-                
+
             let series_data = request
                 .series
                 .iter()
                 .map(|s| s.data.iter().map(|d| d.value).collect::<Vec<f64>>())
                 .collect();
-                
+
             (series_data, labels)
         };
 
@@ -41,10 +42,13 @@ impl Chart for RadarChart {
         } else {
             super::get_max_value(&request.series)
         };
-        let center_x = 400.0;
-        let center_y = 300.0;
-        let radius = 200.0;
-        let num_axes = series.first().map(|s| s.len()).unwrap_or(6);
+        // All-zero data still gets a readable (empty) web
+        let max_value = if max_value > 0.0 { max_value } else { 1.0 };
+        // Centered in the space the legend leaves, with room for the axis labels
+        let (center_x, center_y) = layout.free_center();
+        let radius = (layout.width.min(layout.height) / 3.0)
+            .min(layout.free_radius(40.0 * layout.margin_ratio()));
+        let num_axes = series.iter().map(Vec::len).max().unwrap_or(0).max(1);
         let angle_step = 2.0 * PI / num_axes as f64;
 
         // Draw background circles
@@ -70,8 +74,8 @@ impl Chart for RadarChart {
                 .collect::<Vec<_>>()
                 .join(" ");
             svg_content.push_str(&format!(
-                r#"<path d="{} Z" style="stroke:#CCCCCC;stroke-width:1;fill:none" />"#,
-                path
+                r#"<path d="{} Z" style="stroke:{};stroke-width:1;fill:none" />"#,
+                path, theme.grid
             ));
         }
 
@@ -83,8 +87,8 @@ impl Chart for RadarChart {
 
             // Draw axis line
             svg_content.push_str(&format!(
-                r#"<line x1="{}" y1="{}" x2="{}" y2="{}" style="stroke:#CCCCCC;stroke-width:1" />"#,
-                center_x, center_y, end_x, end_y
+                r#"<line x1="{}" y1="{}" x2="{}" y2="{}" style="stroke:{};stroke-width:1" />"#,
+                center_x, center_y, end_x, end_y, theme.grid
             ));
 
             // Draw axis label
@@ -93,25 +97,17 @@ impl Chart for RadarChart {
             let empty_string = String::new();
             let label = axis_labels.get(i).unwrap_or(&empty_string);
             svg_content.push_str(&format!(
-                r#"<text x="{}" y="{}" text-anchor="middle" font-size="12">{}</text>"#,
+                r#"<text x="{}" y="{}" text-anchor="middle" font-size="{}">{}</text>"#,
                 label_x,
                 label_y,
-                label
+                theme.tick_font_size,
+                svg::escape_text(label)
             ));
         }
 
         // Draw data
         for (series_idx, series_data) in series.iter().enumerate() {
-            let color = if let Some(series) = request.series.get(series_idx) {
-                series.color.as_deref().unwrap_or_else(|| {
-                    request.colors.as_ref()
-                        .and_then(|colors| colors.get(series_idx))
-                        .map(String::as_str)
-                        .unwrap_or_else(|| utils::get_default_colors()[series_idx % utils::get_default_colors().len()])
-                })
-            } else {
-                utils::get_default_colors()[series_idx % utils::get_default_colors().len()]
-            };
+            let color = color::resolve(&theme, request, series_idx, None);
 
             let mut points = Vec::new();
             for (i, &value) in series_data.iter().enumerate() {
@@ -136,23 +132,27 @@ impl Chart for RadarChart {
                 .collect::<Vec<_>>()
                 .join(" ");
             svg_content.push_str(&format!(
-                r#"<path d="{} Z" style="stroke:{};stroke-width:2;fill:{};fill-opacity:0.2" />"#,
-                path, color, color
+                r#"<path d="{} Z" style="stroke:{};stroke-width:{};fill:{};fill-opacity:0.2" />"#,
+                path, color, theme.line_width, color
             ));
 
             // Draw points and values
-            for (_i, ((x, y), &value)) in points.iter().zip(series_data.iter()).enumerate() {
+            for ((x, y), &value) in points.iter().zip(series_data.iter()) {
                 svg_content.push_str(&format!(
                     r#"<circle cx="{}" cy="{}" r="4" fill="{}" />"#,
                     x, y, color
                 ));
-                svg_content.push_str(&utils::svg::generate_value_text(*x, *y, value));
+                svg_content.push_str(&svg::generate_value_text(
+                    &theme,
+                    *x,
+                    *y,
+                    value,
+                    &crate::utils::format_number(value),
+                ));
             }
         }
 
-        if !request.series.is_empty() {
-            svg_content.push_str(&svg::create_legend(&request.series, 660.0, 50.0));
-        }
+        svg_content.push_str(&legend.render(&layout, (0.0, 0.0)));
 
         svg_content.push_str(svg::create_svg_footer());
         svg_content
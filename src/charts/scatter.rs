@@ -1,57 +1,169 @@
 use super::Chart;
-use crate::models::GraphRequest;
-use crate::utils::{self, svg};
+use crate::models::{GraphRequest, LegendPosition};
+use crate::utils::{self, color, scale, scale::Scale, svg, theme::Theme};
 
-pub struct ScatterChart {}
+const BUBBLE_MIN_RADIUS: f64 = 4.0;
+const BUBBLE_MAX_RADIUS: f64 = 30.0;
+
+pub struct ScatterChart {
+    pub is_bubble: bool,
+}
+
+// Bubble radius with circle area proportional to size, scaled so the largest
+// bubble gets the maximum radius
+fn bubble_radius(size: f64, max_size: f64) -> f64 {
+    if size <= 0.0 || max_size <= 0.0 {
+        return BUBBLE_MIN_RADIUS;
+    }
+    (BUBBLE_MAX_RADIUS * (size / max_size).sqrt()).max(BUBBLE_MIN_RADIUS)
+}
+
+// Reference circles for the largest size and two smaller fractions of it
+fn create_size_legend(theme: &Theme, max_size: f64, x: f64, y: f64) -> String {
+    let mut legend = String::new();
+    let mut y_offset = y;
+
+    for fraction in [1.0, 0.5, 0.25] {
+        let size = max_size * fraction;
+        let r = bubble_radius(size, max_size);
+        legend.push_str(&format!(
+            r#"<circle cx="{}" cy="{}" r="{}" fill="none" stroke="{}" stroke-width="1" />"#,
+            x + BUBBLE_MAX_RADIUS,
+            y_offset + r,
+            r,
+            theme.muted
+        ));
+        legend.push_str(&format!(
+            r#"<text x="{}" y="{}" text-anchor="middle" font-family="M PLUS 1p" font-size="{}">{}</text>"#,
+            x + BUBBLE_MAX_RADIUS,
+            y_offset + 2.0 * r + 15.0,
+            theme.tick_font_size,
+            utils::format_number(size)
+        ));
+        y_offset += 2.0 * r + 25.0;
+    }
+
+    legend
+}
 
 impl Chart for ScatterChart {
     fn generate(&self, request: &GraphRequest) -> String {
-        let mut svg_content = svg::create_svg_header(
-            request.title.as_deref(),
-            request.x_label.as_deref(),
-            request.y_label.as_deref(),
-        );
-
-        let series = if request.series.is_empty() {
-            vec![request.data.iter().map(|&v| v).collect::<Vec<f64>>()]
+        let (theme, legend, layout) = super::frame(request);
+        // (x, y, size) triples; points without an explicit x are placed at their
+        // 1-based index
+        let series: Vec<Vec<(f64, f64, f64)>> = if request.series.is_empty() {
+            vec![request
+                .data
+                .iter()
+                .enumerate()
+                .map(|(i, &v)| ((i + 1) as f64, v, 0.0))
+                .collect()]
         } else {
             request
                 .series
                 .iter()
-                .map(|s| s.data.iter().map(|d| d.value).collect::<Vec<f64>>())
+                .map(|s| {
+                    s.data
+                        .iter()
+                        .enumerate()
+                        .map(|(i, d)| {
+                            (
+                                d.x.unwrap_or((i + 1) as f64),
+                                d.value,
+                                d.size.unwrap_or(0.0),
+                            )
+                        })
+                        .collect()
+                })
                 .collect()
         };
 
-        let raw_max = super::get_max_value(&request.series);
-        let max_value = svg::nice_max(raw_max);
-        let segment_width = 640.0 / (series[0].len() as f64 - 1.0);
-
-        svg_content.push_str(&utils::svg::generate_y_axis_ticks(max_value));
-        svg_content.push_str(&utils::svg::generate_x_axis_ticks_for_line(series[0].len()));
-
-        for (series_idx, series_data) in series.iter().enumerate() {
-            let color = request
-                .colors
-                .as_ref()
-                .and_then(|c| c.get(series_idx))
-                .map(String::as_str)
-                .unwrap_or(
-                    utils::get_default_colors()[series_idx % utils::get_default_colors().len()],
-                );
-
-            for (i, &value) in series_data.iter().enumerate() {
-                let x = i as f64 * segment_width;
-                let y = 450.0 - ((value / max_value) * (450.0 - 50.0));
+        let (x_min, x_max) = super::min_max(series.iter().flatten().map(|&(x, _, _)| x));
+        let (y_min, y_max) = super::min_max(series.iter().flatten().map(|&(_, y, _)| y));
+        let (_, max_size) = super::min_max(series.iter().flatten().map(|&(_, _, size)| size));
+        // Leave room so the largest bubbles are not cut off at the plot edges
+        let (x_min, x_max, y_min, y_max) = if self.is_bubble {
+            let pad = |min: f64, max: f64| {
+                let pad = scale::split(min, max, 10.0);
+                ((min - pad).max(f64::MIN), (max + pad).min(f64::MAX))
+            };
+            let (x_min, x_max) = pad(x_min, x_max);
+            let (y_min, y_max) = pad(y_min, y_max);
+            (x_min, x_max, y_min, y_max)
+        } else {
+            (x_min, x_max, y_min, y_max)
+        };
+        let y_scale =
+            super::value_scale(request, y_min, y_max, (layout.plot_bottom, layout.plot_top));
+        let layout = svg::fit_y_ticks(layout, &theme, &y_scale, request.y_label.is_some());
+        let x_scale = Scale::new(request.x_scale, x_min, x_max, (0.0, layout.plot_width));
+
+        let mut svg_content = svg::create_svg_header(
+            &layout,
+            &theme,
+            request.title.as_deref(),
+            request.x_label.as_deref(),
+            request.y_label.as_deref(),
+        );
+
+        svg_content.push_str(&svg::generate_y_axis_ticks(&layout, &theme, &y_scale));
+        svg_content.push_str(&svg::generate_zero_line(&layout, &theme, &y_scale));
+        svg_content.push_str(&svg::generate_x_axis_ticks_for_scale(
+            &layout, &theme, &x_scale,
+        ));
+
+        for (series_idx, points) in series.iter().enumerate() {
+            if self.is_bubble {
+                // Draw large bubbles first so smaller ones stay visible on top
+                let mut bubbles: Vec<_> = points.iter().enumerate().collect();
+                bubbles.sort_by(|a, b| b.1 .2.total_cmp(&a.1 .2));
+                for (i, &(x_value, value, size)) in bubbles {
+                    let color = color::resolve(&theme, request, series_idx, Some(i));
+                    svg_content.push_str(&format!(
+                        r#"<circle cx="{}" cy="{}" r="{}" fill="{}" fill-opacity="0.6" stroke="{}" stroke-width="1" />"#,
+                        x_scale.map(x_value),
+                        y_scale.map(value),
+                        bubble_radius(size, max_size),
+                        color,
+                        color
+                    ));
+                }
+                continue;
+            }
+
+            for (i, &(x_value, value, _)) in points.iter().enumerate() {
+                let color = color::resolve(&theme, request, series_idx, Some(i));
+                let x = x_scale.map(x_value);
+                let y = y_scale.map(value);
                 svg_content.push_str(&format!(
                     r#"<circle cx="{}" cy="{}" r="4" fill="{}" />"#,
                     x, y, color
                 ));
-                svg_content.push_str(&utils::svg::generate_value_text(x, y, value));
+                svg_content.push_str(&svg::generate_value_text(
+                    &theme,
+                    x,
+                    y,
+                    value,
+                    &y_scale.format_value(value),
+                ));
             }
         }
 
-        if !request.series.is_empty() {
-            svg_content.push_str(&svg::create_legend(&request.series, 660.0, 50.0));
+        svg_content.push_str(&legend.render(&layout, (layout.origin_x, layout.origin_y)));
+
+        if self.is_bubble && max_size > 0.0 {
+            // Below the series legend when that sits to the right as well
+            let below_legend = if legend.position == LegendPosition::Right && !legend.is_empty() {
+                legend.height + 20.0
+            } else {
+                0.0
+            };
+            svg_content.push_str(&create_size_legend(
+                &theme,
+                max_size,
+                layout.legend_x(),
+                layout.legend_y() + below_legend,
+            ));
         }
 
         svg_content.push_str(svg::create_svg_footer());
@@ -0,0 +1,98 @@
+use std::fmt;
+
+// Errors reported to API clients as a JSON body:
+//   {"code": "invalid_value", "message": "...", "field": "data", "value": "abc"}
+// `field` and `value` are only present when the error concerns a single parameter.
+#[derive(Debug, Clone, PartialEq)]
+pub enum ChartError {
+    // The POST body is not valid JSON
+    InvalidJson {
+        message: String,
+    },
+    // A parameter or JSON field has a value we cannot use
+    InvalidValue {
+        field: String,
+        value: Option<String>,
+        message: String,
+    },
+    // Neither `data` nor `series` contains anything to draw
+    NoData,
+    // Request serialization, SVG parsing or PNG rasterization failed
+    Render {
+        message: String,
+    },
+    MethodNotAllowed,
+}
+
+impl ChartError {
+    pub fn invalid_value(
+        field: impl Into<String>,
+        value: impl Into<String>,
+        message: impl Into<String>,
+    ) -> Self {
+        ChartError::InvalidValue {
+            field: field.into(),
+            value: Some(value.into()),
+            message: message.into(),
+        }
+    }
+
+    pub fn code(&self) -> &'static str {
+        match self {
+            ChartError::InvalidJson { .. } => "invalid_json",
+            ChartError::InvalidValue { .. } => "invalid_value",
+            ChartError::NoData => "no_data",
+            ChartError::Render { .. } => "render_failed",
+            ChartError::MethodNotAllowed => "method_not_allowed",
+        }
+    }
+
+    pub fn status(&self) -> u16 {
+        match self {
+            ChartError::InvalidJson { .. }
+            | ChartError::InvalidValue { .. }
+            | ChartError::NoData => 400,
+            ChartError::Render { .. } => 500,
+            ChartError::MethodNotAllowed => 405,
+        }
+    }
+
+    pub fn field(&self) -> Option<&str> {
+        match self {
+            ChartError::InvalidValue { field, .. } => Some(field),
+            _ => None,
+        }
+    }
+
+    pub fn value(&self) -> Option<&str> {
+        match self {
+            ChartError::InvalidValue { value, .. } => value.as_deref(),
+            _ => None,
+        }
+    }
+
+    pub fn to_json(&self) -> String {
+        let mut body = serde_json::Map::new();
+        body.insert("code".into(), self.code().into());
+        body.insert("message".into(), self.to_string().into());
+        if let Some(field) = self.field() {
+            body.insert("field".into(), field.into());
+        }
+        if let Some(value) = self.value() {
+            body.insert("value".into(), value.into());
+        }
+        serde_json::Value::Object(body).to_string()
+    }
+}
+
+impl fmt::Display for ChartError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ChartError::InvalidJson { message } => write!(f, "Invalid JSON: {}", message),
+            ChartError::InvalidValue { message, .. } => f.write_str(message),
+            ChartError::NoData => f.write_str("No data provided"),
+            ChartError::Render { message } => f.write_str(message),
+            ChartError::MethodNotAllowed => f.write_str("Method not allowed"),
+        }
+    }
+}
@@ -0,0 +1,58 @@
+use crate::error::ChartError;
+use crate::models::GraphRequest;
+use worker::*;
+
+#[event(fetch)]
+pub async fn main(mut req: Request, _env: Env, _ctx: Context) -> Result<Response> {
+    let url = req.url()?;
+
+    match req.method() {
+        Method::Get => {
+            let params: Vec<_> = url.query_pairs().collect();
+            match crate::request::parse_query(&params) {
+                Ok(graph_req) => render_chart(&graph_req),
+                Err(e) => error_response(&e),
+            }
+        }
+        Method::Post => {
+            let body = req.bytes().await?;
+            match crate::request::parse_json(&body) {
+                Ok(graph_req) => render_chart(&graph_req),
+                Err(e) => error_response(&e),
+            }
+        }
+        Method::Options => {
+            let mut headers = Headers::new();
+            headers.set("Access-Control-Allow-Origin", "*")?;
+            headers.set("Access-Control-Allow-Methods", "GET, POST, OPTIONS")?;
+            headers.set("Access-Control-Allow-Headers", "Content-Type")?;
+            let resp = Response::empty()?;
+            Ok(resp.with_headers(headers))
+        }
+        _ => error_response(&ChartError::MethodNotAllowed),
+    }
+}
+
+fn render_chart(graph_req: &GraphRequest) -> Result<Response> {
+    let rendered = match crate::render(graph_req) {
+        Ok(rendered) => rendered,
+        Err(e) => return error_response(&e),
+    };
+
+    let mut headers = Headers::new();
+    headers.set("Content-Type", rendered.content_type)?;
+    headers.set("Cache-Control", "public, max-age=604800")?; // 7日間のキャッシュ
+    headers.set("Access-Control-Allow-Origin", "*")?;
+
+    let resp = Response::from_bytes(rendered.body)?;
+    Ok(resp.with_headers(headers))
+}
+
+fn error_response(error: &ChartError) -> Result<Response> {
+    let mut headers = Headers::new();
+    headers.set("Content-Type", "application/json")?;
+    headers.set("Access-Control-Allow-Origin", "*")?;
+
+    let resp = Response::from_bytes(error.to_json().into_bytes())?;
+    Ok(resp.with_status(error.status()).with_headers(headers))
+}
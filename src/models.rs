@@ -1,8 +1,9 @@
-use serde::Deserialize;
+use serde::{Deserialize, Serialize};
 
-#[derive(Deserialize)]
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Default)]
 #[serde(rename_all = "snake_case")]
 pub enum GraphType {
+    #[default]
     Line,
     Bar,
     Scatter,
@@ -10,38 +11,595 @@ pub enum GraphType {
     Donut,
     Area,
     Radar,
+    Bubble,
+    Heatmap,
+    Calendar,
+    Histogram,
+    Box,
+    Candlestick,
+    Ohlc,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Default)]
+#[serde(rename_all = "snake_case")]
+pub enum ScaleType {
+    #[default]
+    Linear,
+    Log,
+    Time,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Default)]
+#[serde(rename_all = "snake_case")]
+pub enum Stacking {
+    #[default]
+    None,
+    Normal,
+    Percent,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Default)]
+#[serde(rename_all = "snake_case")]
+pub enum Orientation {
+    #[default]
+    Vertical,
+    Horizontal,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Default)]
+#[serde(rename_all = "snake_case")]
+pub enum LegendPosition {
+    Top,
+    Bottom,
+    #[default]
+    Right,
+    None,
+}
+
+// Rules that choose a bin width from the values themselves
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Default)]
+#[serde(rename_all = "snake_case")]
+pub enum BinRule {
+    #[default]
+    Sturges,
+    FreedmanDiaconis,
+}
+
+// How a histogram splits its values: `"sturges"`, `"freedman_diaconis"`, a number of
+// bins or `{"width": 2.5}`
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq)]
+#[serde(untagged, try_from = "RawBins")]
+pub enum Bins {
+    Rule(BinRule),
+    Count(usize),
+    Width { width: f64 },
+}
+
+#[derive(Deserialize)]
+#[serde(
+    untagged,
+    expecting = "a binning rule (sturges, freedman_diaconis), a bin count or {\"width\": ...}"
+)]
+enum RawBins {
+    Rule(BinRule),
+    Count(usize),
+    Width { width: f64 },
+}
+
+impl TryFrom<RawBins> for Bins {
+    type Error = String;
+
+    fn try_from(raw: RawBins) -> Result<Self, Self::Error> {
+        match raw {
+            RawBins::Rule(rule) => Ok(Bins::Rule(rule)),
+            RawBins::Count(0) => Err("a histogram needs at least one bin".into()),
+            RawBins::Count(count) => Ok(Bins::Count(count)),
+            RawBins::Width { width } if width > 0.0 => Ok(Bins::Width { width }),
+            RawBins::Width { .. } => Err("the bin width must be positive".into()),
+        }
+    }
+}
+
+impl Default for Bins {
+    fn default() -> Self {
+        Bins::Rule(BinRule::default())
+    }
+}
+
+impl Bins {
+    /// Bins of a fixed width, aligned to multiples of it.
+    pub fn width(width: f64) -> Self {
+        Bins::Width { width }
+    }
+}
+
+impl From<BinRule> for Bins {
+    fn from(rule: BinRule) -> Self {
+        Bins::Rule(rule)
+    }
+}
+
+impl From<usize> for Bins {
+    fn from(count: usize) -> Self {
+        Bins::Count(count)
+    }
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Default)]
+#[serde(rename_all = "snake_case")]
+pub enum OutputFormat {
+    #[default]
+    Png,
+    Svg,
+}
+
+// Named color palettes; see utils::palette for the colors
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq)]
+pub enum PaletteName {
+    #[serde(rename = "tableau10")]
+    Tableau10,
+    #[serde(rename = "okabe-ito", alias = "okabe_ito")]
+    OkabeIto,
+    #[serde(rename = "viridis")]
+    Viridis,
+    #[serde(rename = "rdbu")]
+    RdBu,
 }
 
-impl Default for GraphType {
+// Built-in look of a chart; see utils::theme for the actual colors and sizes
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Default)]
+#[serde(rename_all = "kebab-case")]
+pub enum ThemePreset {
+    #[default]
+    Light,
+    Dark,
+    Minimal,
+    #[serde(alias = "high_contrast")]
+    HighContrast,
+}
+
+// `"theme": "dark"` or an inline object that overrides parts of a preset
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+#[serde(
+    untagged,
+    expecting = "a theme name (light, dark, minimal, high-contrast) or a theme object"
+)]
+pub enum ThemeSpec {
+    Preset(ThemePreset),
+    Custom(Box<CustomTheme>),
+}
+
+impl Default for ThemeSpec {
     fn default() -> Self {
-        GraphType::Line
+        ThemeSpec::Preset(ThemePreset::default())
+    }
+}
+
+impl From<ThemePreset> for ThemeSpec {
+    fn from(preset: ThemePreset) -> Self {
+        ThemeSpec::Preset(preset)
+    }
+}
+
+impl From<CustomTheme> for ThemeSpec {
+    fn from(theme: CustomTheme) -> Self {
+        ThemeSpec::Custom(Box::new(theme))
     }
 }
 
-#[derive(Deserialize, Clone)]
+// Unset fields keep the value of the base preset
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Default)]
+pub struct CustomTheme {
+    #[serde(default)]
+    pub base: ThemePreset,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub background: Option<String>,
+    // Text color
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub foreground: Option<String>,
+    // Secondary text and outlines, e.g. the "No data" message
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub muted: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub grid: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub axis: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub palette: Option<Vec<String>>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub title_font_size: Option<f64>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub label_font_size: Option<f64>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub tick_font_size: Option<f64>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub axis_width: Option<f64>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub line_width: Option<f64>,
+    // Rising and falling candles
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub up: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub down: Option<String>,
+}
+
+impl CustomTheme {
+    /// A theme that starts from `base` and overrides nothing yet.
+    pub fn new(base: ThemePreset) -> Self {
+        CustomTheme {
+            base,
+            ..CustomTheme::default()
+        }
+    }
+}
+
+// Serialized as the full object form, which deserializes back unchanged
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+#[serde(try_from = "RawDataPoint")]
 pub struct DataPoint {
     pub value: f64,
+    #[serde(skip_serializing_if = "Option::is_none")]
     pub label: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
     pub color: Option<String>,
+    // Numeric x position for XY charts such as scatter; None means "use the index"
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub x: Option<f64>,
+    // Third dimension for bubble charts, mapped to circle area
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub size: Option<f64>,
+    // Prices for candlestick and OHLC charts; the value defaults to the close
+    #[serde(flatten)]
+    pub ohlc: Option<Ohlc>,
+    // Traded volume, shown in a panel below the prices
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub volume: Option<f64>,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq)]
+pub struct Ohlc {
+    pub open: f64,
+    pub high: f64,
+    pub low: f64,
+    pub close: f64,
+}
+
+// Accepted JSON forms for a data point: a bare number, an [x, y] pair,
+// an [x, y, size] triple, or the full object
+#[derive(Deserialize)]
+#[serde(
+    untagged,
+    expecting = "a number, an [x, y] pair, an [x, y, size] triple or a data point object"
+)]
+enum RawDataPoint {
+    Value(f64),
+    Pair(f64, f64),
+    Triple(f64, f64, f64),
+    Object {
+        value: Option<f64>,
+        label: Option<String>,
+        color: Option<String>,
+        x: Option<f64>,
+        size: Option<f64>,
+        open: Option<f64>,
+        high: Option<f64>,
+        low: Option<f64>,
+        close: Option<f64>,
+        volume: Option<f64>,
+    },
+}
+
+impl TryFrom<RawDataPoint> for DataPoint {
+    type Error = String;
+
+    fn try_from(raw: RawDataPoint) -> Result<Self, Self::Error> {
+        Ok(match raw {
+            RawDataPoint::Value(value) => DataPoint::new(value),
+            RawDataPoint::Pair(x, value) => DataPoint::xy(x, value),
+            RawDataPoint::Triple(x, value, size) => DataPoint::xy(x, value).size(size),
+            RawDataPoint::Object {
+                value,
+                label,
+                color,
+                x,
+                size,
+                open,
+                high,
+                low,
+                close,
+                volume,
+            } => {
+                let ohlc = match (open, high, low, close) {
+                    (Some(open), Some(high), Some(low), Some(close)) => Some(Ohlc {
+                        open,
+                        high,
+                        low,
+                        close,
+                    }),
+                    (None, None, None, None) => None,
+                    _ => return Err("open, high, low and close must be given together".into()),
+                };
+                if volume.is_some_and(|v| v < 0.0) {
+                    return Err("volume must not be negative".into());
+                }
+                let Some(value) = value.or(close) else {
+                    return Err("a data point needs a value".into());
+                };
+                DataPoint {
+                    value,
+                    label,
+                    color,
+                    x,
+                    size,
+                    ohlc,
+                    volume,
+                }
+            }
+        })
+    }
 }
 
-#[derive(Deserialize, Clone)]
+impl DataPoint {
+    /// A point with just a value, placed by its index on category axes.
+    pub fn new(value: f64) -> Self {
+        DataPoint {
+            value,
+            label: None,
+            color: None,
+            x: None,
+            size: None,
+            ohlc: None,
+            volume: None,
+        }
+    }
+
+    /// A candlestick or OHLC bar; its value is the close.
+    pub fn ohlc(open: f64, high: f64, low: f64, close: f64) -> Self {
+        DataPoint {
+            ohlc: Some(Ohlc {
+                open,
+                high,
+                low,
+                close,
+            }),
+            ..DataPoint::new(close)
+        }
+    }
+
+    /// A point at a numeric x position, for scatter and bubble charts.
+    pub fn xy(x: f64, y: f64) -> Self {
+        DataPoint {
+            x: Some(x),
+            ..DataPoint::new(y)
+        }
+    }
+
+    /// Sets the category label shown on the x axis (or next to the slice/axis).
+    pub fn label(mut self, label: impl Into<String>) -> Self {
+        self.label = Some(label.into());
+        self
+    }
+
+    /// Sets a color for this point only.
+    pub fn color(mut self, color: impl Into<String>) -> Self {
+        self.color = Some(color.into());
+        self
+    }
+
+    /// Sets the bubble size.
+    pub fn size(mut self, size: f64) -> Self {
+        self.size = Some(size);
+        self
+    }
+
+    /// Sets the traded volume shown below candlesticks.
+    pub fn volume(mut self, volume: f64) -> Self {
+        self.volume = Some(volume);
+        self
+    }
+}
+
+impl From<f64> for DataPoint {
+    fn from(value: f64) -> Self {
+        DataPoint::new(value)
+    }
+}
+
+// ("Jan", 10.0)
+impl From<(&str, f64)> for DataPoint {
+    fn from((label, value): (&str, f64)) -> Self {
+        DataPoint::new(value).label(label)
+    }
+}
+
+// (x, y)
+impl From<(f64, f64)> for DataPoint {
+    fn from((x, y): (f64, f64)) -> Self {
+        DataPoint::xy(x, y)
+    }
+}
+
+// (x, y, size)
+impl From<(f64, f64, f64)> for DataPoint {
+    fn from((x, y, size): (f64, f64, f64)) -> Self {
+        DataPoint::xy(x, y).size(size)
+    }
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Default)]
 pub struct Series {
+    #[serde(skip_serializing_if = "Option::is_none")]
     pub name: Option<String>,
+    // May be left out when a box plot series has a summary
+    #[serde(default)]
     pub data: Vec<DataPoint>,
+    #[serde(skip_serializing_if = "Option::is_none")]
     pub color: Option<String>,
+    // Precomputed box plot statistics, used instead of the data
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub summary: Option<Summary>,
 }
 
-#[derive(Deserialize)]
+impl Series {
+    /// An empty series with a legend name.
+    pub fn new(name: impl Into<String>) -> Self {
+        Series {
+            name: Some(name.into()),
+            ..Series::default()
+        }
+    }
+
+    /// Appends points; anything convertible into a [`DataPoint`] works, e.g. `10.0`,
+    /// `("Jan", 10.0)`, `(x, y)` or `(x, y, size)`.
+    pub fn data<P: Into<DataPoint>>(mut self, points: impl IntoIterator<Item = P>) -> Self {
+        self.data.extend(points.into_iter().map(Into::into));
+        self
+    }
+
+    /// Sets the series color.
+    pub fn color(mut self, color: impl Into<String>) -> Self {
+        self.color = Some(color.into());
+        self
+    }
+
+    /// Box plot statistics computed elsewhere; the series' data is then ignored.
+    pub fn summary(mut self, summary: Summary) -> Self {
+        self.summary = Some(summary);
+        self
+    }
+}
+
+// Five-number summary of a box plot. The whiskers reach min and max; there are no
+// outliers to draw.
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq)]
+pub struct Summary {
+    pub min: f64,
+    pub q1: f64,
+    pub median: f64,
+    pub q3: f64,
+    pub max: f64,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub mean: Option<f64>,
+}
+
+impl Summary {
+    /// Lowest value, quartiles and highest value.
+    pub fn new(min: f64, q1: f64, median: f64, q3: f64, max: f64) -> Self {
+        Summary {
+            min,
+            q1,
+            median,
+            q3,
+            max,
+            mean: None,
+        }
+    }
+
+    /// Sets the mean shown with `show_mean`.
+    pub fn mean(mut self, mean: f64) -> Self {
+        self.mean = Some(mean);
+        self
+    }
+}
+
+// Heatmap cells as values[row][column]; null cells are left empty. Missing labels
+// are numbered from 1.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Default)]
+pub struct Matrix {
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub rows: Vec<String>,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub columns: Vec<String>,
+    pub values: Vec<Vec<Option<f64>>>,
+    // Print each value inside its cell
+    #[serde(default)]
+    pub show_values: bool,
+}
+
+impl Matrix {
+    /// Cells given row by row; rows may differ in length.
+    pub fn new<R, V>(values: impl IntoIterator<Item = R>) -> Self
+    where
+        R: IntoIterator<Item = V>,
+        V: Into<Option<f64>>,
+    {
+        Matrix {
+            values: values
+                .into_iter()
+                .map(|row| row.into_iter().map(Into::into).collect())
+                .collect(),
+            ..Matrix::default()
+        }
+    }
+
+    /// Labels shown left of each row.
+    pub fn rows<S: Into<String>>(mut self, labels: impl IntoIterator<Item = S>) -> Self {
+        self.rows = labels.into_iter().map(Into::into).collect();
+        self
+    }
+
+    /// Labels shown below each column.
+    pub fn columns<S: Into<String>>(mut self, labels: impl IntoIterator<Item = S>) -> Self {
+        self.columns = labels.into_iter().map(Into::into).collect();
+        self
+    }
+
+    /// Prints each value inside its cell where it fits.
+    pub fn show_values(mut self, show: bool) -> Self {
+        self.show_values = show;
+        self
+    }
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Default)]
 pub struct GraphRequest {
     #[serde(default)]
     pub graph_type: GraphType,
-    #[serde(default)]
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
     pub series: Vec<Series>,
-    #[serde(default)]
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
     pub data: Vec<f64>, // 後方互換性のため残す
+    #[serde(skip_serializing_if = "Option::is_none")]
     pub title: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
     pub x_label: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
     pub y_label: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
     pub colors: Option<Vec<String>>,
+    #[serde(default)]
+    pub format: OutputFormat,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub width: Option<f64>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub height: Option<f64>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub scale: Option<f64>,
+    #[serde(default)]
+    pub x_scale: ScaleType,
+    #[serde(default)]
+    pub y_scale: ScaleType,
+    #[serde(default)]
+    pub stacking: Stacking,
+    #[serde(default)]
+    pub orientation: Orientation,
+    #[serde(default)]
+    pub legend: LegendPosition,
+    #[serde(default)]
+    pub theme: ThemeSpec,
+    // Overrides the theme's series colors
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub palette: Option<PaletteName>,
+    // Cells for heatmaps
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub matrix: Option<Matrix>,
+    // Histogram binning; Sturges' rule when unset
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub bins: Option<Bins>,
+    // Overlay a smoothed density curve on histograms
+    #[serde(default)]
+    pub density: bool,
+    // Mark the mean of each box in box plots
+    #[serde(default)]
+    pub show_mean: bool,
 }
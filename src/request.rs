@@ -0,0 +1,682 @@
+use crate::charts::histogram;
+use crate::error::ChartError;
+use crate::models::{
+    self, BinRule, Bins, DataPoint, GraphRequest, GraphType, LegendPosition, Matrix, Ohlc,
+    Orientation, OutputFormat, PaletteName, ScaleType, Stacking, Summary, ThemePreset, ThemeSpec,
+};
+use crate::utils::layout::{
+    DEFAULT_HEIGHT, DEFAULT_WIDTH, MAX_PIXELS, MAX_SCALE, MAX_SIZE, MIN_SIZE,
+};
+use serde::Deserialize;
+use serde_json::Value;
+
+// POST body. Unless `"lenient": true` is set, every field has to be valid.
+pub fn parse_json(body: &[u8]) -> Result<GraphRequest, ChartError> {
+    let mut value: Value = serde_json::from_slice(body).map_err(|e| ChartError::InvalidJson {
+        message: e.to_string(),
+    })?;
+
+    if !value.is_object() {
+        return Err(ChartError::InvalidJson {
+            message: "expected a JSON object".to_string(),
+        });
+    }
+
+    let lenient = value.get("lenient").and_then(Value::as_bool) == Some(true);
+    if lenient {
+        relax(&mut value);
+    }
+
+    let request: GraphRequest = serde_path_to_error::deserialize(&value).map_err(|e| {
+        let path = e.path();
+        ChartError::InvalidValue {
+            field: path.to_string(),
+            value: lookup(&value, path).map(|v| match v {
+                Value::String(s) => s.clone(),
+                other => other.to_string(),
+            }),
+            message: e.inner().to_string(),
+        }
+    })?;
+    if !lenient {
+        check_canvas(&request)?;
+        check_bin_width(&request, "bins.width")?;
+    }
+    Ok(request)
+}
+
+// Canvas sizes and scales the layout would otherwise clamp, which only lenient mode
+// allows
+fn check_canvas(request: &GraphRequest) -> Result<(), ChartError> {
+    for (field, size) in [("width", request.width), ("height", request.height)] {
+        match size {
+            Some(size) if !(MIN_SIZE..=MAX_SIZE).contains(&size) => {
+                return Err(ChartError::invalid_value(
+                    field,
+                    size.to_string(),
+                    format!("Invalid {field}: expected a number from {MIN_SIZE} to {MAX_SIZE}"),
+                ))
+            }
+            _ => {}
+        }
+    }
+    if let Some(scale) = request.scale {
+        let width = request.width.unwrap_or(DEFAULT_WIDTH);
+        let height = request.height.unwrap_or(DEFAULT_HEIGHT);
+        let largest = (MAX_PIXELS / (width * height)).sqrt().min(MAX_SCALE);
+        if !(scale > 0.0 && scale <= largest) {
+            return Err(ChartError::invalid_value(
+                "scale",
+                scale.to_string(),
+                format!(
+                    "Invalid scale: expected a number above 0 and at most {} for a {width}x{height} canvas",
+                    crate::utils::format_number(largest)
+                ),
+            ));
+        }
+    }
+    Ok(())
+}
+
+// Fixed bin widths so narrow that the histogram would have to widen them
+fn check_bin_width(request: &GraphRequest, field: &str) -> Result<(), ChartError> {
+    let Some(Bins::Width { width }) = request.bins else {
+        return Ok(());
+    };
+    if request.graph_type != GraphType::Histogram {
+        return Ok(());
+    }
+    let values: Vec<f64> = histogram::samples(request).concat();
+    if histogram::width_fits(&values, width) {
+        return Ok(());
+    }
+    Err(ChartError::invalid_value(
+        field,
+        width.to_string(),
+        format!(
+            "Invalid {field}: the data would need more than {} bins of this width",
+            histogram::MAX_BINS
+        ),
+    ))
+}
+
+// Value at the position an error was reported for
+fn lookup<'a>(value: &'a Value, path: &serde_path_to_error::Path) -> Option<&'a Value> {
+    use serde_path_to_error::Segment;
+
+    path.iter().try_fold(value, |v, segment| match segment {
+        Segment::Seq { index } => v.get(index),
+        Segment::Map { key } => v.get(key.as_str()),
+        Segment::Enum { .. } | Segment::Unknown => Some(v),
+    })
+}
+
+// Lenient mode: unknown chart types fall back to line, an unusable theme, palette or
+// binning to the default one and unusable data points and box summaries are dropped,
+// as the API did before validation was added
+fn relax(value: &mut Value) {
+    if let Some(graph_type) = value.get_mut("graph_type") {
+        if GraphType::deserialize(&*graph_type).is_err() {
+            *graph_type = Value::from("line");
+        }
+    }
+    if let Some(object) = value.as_object_mut() {
+        if object
+            .get("theme")
+            .is_some_and(|theme| ThemeSpec::deserialize(theme).is_err())
+        {
+            object.remove("theme");
+        }
+        if object
+            .get("palette")
+            .is_some_and(|palette| Option::<PaletteName>::deserialize(palette).is_err())
+        {
+            object.remove("palette");
+        }
+        if object
+            .get("bins")
+            .is_some_and(|bins| Option::<Bins>::deserialize(bins).is_err())
+        {
+            object.remove("bins");
+        }
+    }
+    if let Some(Value::Array(data)) = value.get_mut("data") {
+        data.retain(|v| f64::deserialize(v).is_ok());
+    }
+    if let Some(Value::Array(series)) = value.get_mut("series") {
+        for series in series {
+            if let Some(Value::Array(data)) = series.get_mut("data") {
+                data.retain(|p| DataPoint::deserialize(p).is_ok());
+            }
+            if let Some(series) = series.as_object_mut() {
+                if series
+                    .get("summary")
+                    .is_some_and(|summary| Option::<Summary>::deserialize(summary).is_err())
+                {
+                    series.remove("summary");
+                }
+            }
+        }
+    }
+}
+
+// GET query string, e.g. ?type=bar&data=1,2,3&labels=a,b,c
+pub fn parse_query<K: AsRef<str>, V: AsRef<str>>(
+    params: &[(K, V)],
+) -> Result<GraphRequest, ChartError> {
+    let mut lenient = false;
+    for (key, value) in params {
+        if key.as_ref() == "lenient" {
+            lenient = parse_choice(
+                "lenient",
+                value.as_ref(),
+                &[("true", true), ("false", false)],
+            )?;
+        }
+    }
+
+    let mut graph_type = GraphType::default();
+    let mut data: Vec<f64> = Vec::new();
+    let mut series: Vec<models::Series> = Vec::new();
+    let mut title: Option<String> = None;
+    let mut x_label: Option<String> = None;
+    let mut y_label: Option<String> = None;
+    let mut colors: Option<Vec<String>> = None;
+    let mut format = OutputFormat::default();
+    let mut width: Option<f64> = None;
+    let mut height: Option<f64> = None;
+    let mut scale: Option<f64> = None;
+    let mut x_scale = ScaleType::default();
+    let mut y_scale = ScaleType::default();
+    let mut stacking = Stacking::default();
+    let mut orientation = Orientation::default();
+    let mut legend = LegendPosition::default();
+    let mut theme = ThemeSpec::default();
+    let mut palette: Option<PaletteName> = None;
+    let mut matrix: Option<Matrix> = None;
+    let mut bins: Option<Bins> = None;
+    let mut density = false;
+    let mut show_mean = false;
+
+    // シリーズデータのための一時的な保存領域
+    let mut series_values: Vec<f64> = Vec::new();
+    let mut series_labels: Vec<String> = Vec::new();
+    let mut series_x: Vec<f64> = Vec::new();
+    let mut series_sizes: Vec<Option<f64>> = Vec::new();
+    let mut series_ohlc: Vec<Ohlc> = Vec::new();
+    let mut series_volume: Vec<f64> = Vec::new();
+
+    for (key, value) in params {
+        let value = value.as_ref();
+        match key.as_ref() {
+            "type" => {
+                graph_type = match parse_choice(
+                    "type",
+                    value,
+                    &[
+                        ("line", GraphType::Line),
+                        ("bar", GraphType::Bar),
+                        ("scatter", GraphType::Scatter),
+                        ("pie", GraphType::Pie),
+                        ("donut", GraphType::Donut),
+                        ("area", GraphType::Area),
+                        ("radar", GraphType::Radar),
+                        ("bubble", GraphType::Bubble),
+                        ("heatmap", GraphType::Heatmap),
+                        ("calendar", GraphType::Calendar),
+                        ("histogram", GraphType::Histogram),
+                        ("box", GraphType::Box),
+                        ("candlestick", GraphType::Candlestick),
+                        ("ohlc", GraphType::Ohlc),
+                    ],
+                ) {
+                    Ok(graph_type) => graph_type,
+                    Err(_) if lenient => GraphType::Line,
+                    Err(e) => return Err(e),
+                };
+            }
+            "data" => {
+                data = Vec::new();
+                // An empty `data=` is reported as missing data, not as a bad number
+                for item in value.split(',').filter(|_| !value.is_empty()) {
+                    match parse_number("data", item) {
+                        Ok(v) => data.push(v),
+                        Err(_) if lenient => {}
+                        Err(e) => return Err(e),
+                    }
+                }
+                series_values = data.clone();
+            }
+            "points" => {
+                // x:y pairs (or x:y:size for bubbles), e.g. points=1.5:20,3:42:7
+                let mut xs = Vec::new();
+                let mut ys = Vec::new();
+                let mut sizes = Vec::new();
+                for point in value.split(',') {
+                    let parts: Result<Vec<f64>, _> = point
+                        .split(':')
+                        .map(|p| parse_number("points", p))
+                        .collect();
+                    match parts.as_deref() {
+                        Ok([x, y]) => {
+                            xs.push(*x);
+                            ys.push(*y);
+                            sizes.push(None);
+                        }
+                        Ok([x, y, size]) => {
+                            xs.push(*x);
+                            ys.push(*y);
+                            sizes.push(Some(*size));
+                        }
+                        _ if lenient => {}
+                        _ => {
+                            return Err(ChartError::invalid_value(
+                                "points",
+                                point,
+                                "Invalid points: expected x:y or x:y:size numbers",
+                            ))
+                        }
+                    }
+                }
+                series_x = xs;
+                series_sizes = sizes;
+                data = ys;
+                series_values = data.clone();
+            }
+            "ohlc" => {
+                // open:high:low:close per point, e.g. ohlc=10:12:9:11,11:13:10:12.5
+                let mut prices = Vec::new();
+                for point in value.split(',') {
+                    let parts: Result<Vec<f64>, _> =
+                        point.split(':').map(|p| parse_number("ohlc", p)).collect();
+                    match parts.as_deref() {
+                        Ok(&[open, high, low, close]) => prices.push(Ohlc {
+                            open,
+                            high,
+                            low,
+                            close,
+                        }),
+                        _ if lenient => {}
+                        _ => {
+                            return Err(ChartError::invalid_value(
+                                "ohlc",
+                                point,
+                                "Invalid ohlc: expected open:high:low:close numbers",
+                            ))
+                        }
+                    }
+                }
+                data = prices.iter().map(|p| p.close).collect();
+                series_values = data.clone();
+                series_ohlc = prices;
+            }
+            "volume" => {
+                series_volume = Vec::new();
+                for item in value.split(',') {
+                    match parse_number("volume", item) {
+                        Ok(v) if v < 0.0 && !lenient => {
+                            return Err(ChartError::invalid_value(
+                                "volume",
+                                item,
+                                "Invalid volume: expected a number of at least 0",
+                            ))
+                        }
+                        Ok(v) => series_volume.push(v),
+                        Err(_) if lenient => {}
+                        Err(e) => return Err(e),
+                    }
+                }
+            }
+            "labels" => {
+                series_labels = value.split(',').map(String::from).collect();
+            }
+            "matrix" => {
+                // Rows separated by ';', cells by ','; an empty cell has no value,
+                // e.g. matrix=1,2,3;4,,6
+                let mut values = Vec::new();
+                for row in value.split(';') {
+                    let mut cells = Vec::new();
+                    for cell in row.split(',') {
+                        match cell.trim() {
+                            "" => cells.push(None),
+                            cell => match parse_number("matrix", cell) {
+                                Ok(v) => cells.push(Some(v)),
+                                Err(_) if lenient => cells.push(None),
+                                Err(e) => return Err(e),
+                            },
+                        }
+                    }
+                    values.push(cells);
+                }
+                matrix.get_or_insert_with(Matrix::default).values = values;
+            }
+            "rows" => {
+                matrix.get_or_insert_with(Matrix::default).rows =
+                    value.split(',').map(String::from).collect();
+            }
+            "columns" => {
+                matrix.get_or_insert_with(Matrix::default).columns =
+                    value.split(',').map(String::from).collect();
+            }
+            "show_values" => {
+                matrix.get_or_insert_with(Matrix::default).show_values =
+                    parse_choice("show_values", value, &[("true", true), ("false", false)])?;
+            }
+            "bins" => {
+                // A rule name or a number of bins, e.g. bins=freedman_diaconis or bins=20
+                let parsed = match value {
+                    "sturges" => Ok(Bins::Rule(BinRule::Sturges)),
+                    "freedman_diaconis" => Ok(Bins::Rule(BinRule::FreedmanDiaconis)),
+                    _ => match value.trim().parse() {
+                        Ok(count) if count > 0 => Ok(Bins::Count(count)),
+                        _ => Err(ChartError::invalid_value(
+                            "bins",
+                            value,
+                            "Invalid bins: expected sturges, freedman_diaconis or a positive bin count",
+                        )),
+                    },
+                };
+                bins = match parsed {
+                    Ok(parsed) => Some(parsed),
+                    Err(_) if lenient => bins,
+                    Err(e) => return Err(e),
+                };
+            }
+            "bin_width" => match parse_number("bin_width", value) {
+                Ok(width) if width > 0.0 => bins = Some(Bins::width(width)),
+                Ok(_) if lenient => {}
+                Ok(_) => {
+                    return Err(ChartError::invalid_value(
+                        "bin_width",
+                        value,
+                        "Invalid bin_width: expected a positive number",
+                    ))
+                }
+                Err(_) if lenient => {}
+                Err(e) => return Err(e),
+            },
+            "density" => {
+                density = parse_choice("density", value, &[("true", true), ("false", false)])?;
+            }
+            "show_mean" => {
+                show_mean = parse_choice("show_mean", value, &[("true", true), ("false", false)])?;
+            }
+            "title" => title = Some(value.to_string()),
+            "x_label" => x_label = Some(value.to_string()),
+            "y_label" => y_label = Some(value.to_string()),
+            "colors" => {
+                colors = Some(value.split(',').map(String::from).collect());
+            }
+            "format" => {
+                format = parse_choice(
+                    "format",
+                    value,
+                    &[("png", OutputFormat::Png), ("svg", OutputFormat::Svg)],
+                )?;
+            }
+            "width" => width = Some(parse_number("width", value)?),
+            "height" => height = Some(parse_number("height", value)?),
+            "scale" => scale = Some(parse_number("scale", value)?),
+            "x_scale" => x_scale = parse_scale_type("x_scale", value)?,
+            "y_scale" => y_scale = parse_scale_type("y_scale", value)?,
+            "stacking" => {
+                stacking = parse_choice(
+                    "stacking",
+                    value,
+                    &[
+                        ("none", Stacking::None),
+                        ("normal", Stacking::Normal),
+                        ("percent", Stacking::Percent),
+                    ],
+                )?;
+            }
+            "orientation" => {
+                orientation = parse_choice(
+                    "orientation",
+                    value,
+                    &[
+                        ("vertical", Orientation::Vertical),
+                        ("horizontal", Orientation::Horizontal),
+                    ],
+                )?;
+            }
+            "legend" => {
+                legend = parse_choice(
+                    "legend",
+                    value,
+                    &[
+                        ("top", LegendPosition::Top),
+                        ("bottom", LegendPosition::Bottom),
+                        ("right", LegendPosition::Right),
+                        ("none", LegendPosition::None),
+                    ],
+                )?;
+            }
+            "theme" => {
+                let preset = parse_choice(
+                    "theme",
+                    value,
+                    &[
+                        ("light", ThemePreset::Light),
+                        ("dark", ThemePreset::Dark),
+                        ("minimal", ThemePreset::Minimal),
+                        ("high-contrast", ThemePreset::HighContrast),
+                    ],
+                );
+                theme = match preset {
+                    Ok(preset) => ThemeSpec::Preset(preset),
+                    Err(_) if lenient => ThemeSpec::default(),
+                    Err(e) => return Err(e),
+                };
+            }
+            "palette" => {
+                let name = parse_choice(
+                    "palette",
+                    value,
+                    &[
+                        ("tableau10", PaletteName::Tableau10),
+                        ("okabe-ito", PaletteName::OkabeIto),
+                        ("viridis", PaletteName::Viridis),
+                        ("rdbu", PaletteName::RdBu),
+                    ],
+                );
+                palette = match name {
+                    Ok(name) => Some(name),
+                    Err(_) if lenient => None,
+                    Err(e) => return Err(e),
+                };
+            }
+            _ => {}
+        }
+    }
+
+    // シリーズデータを構築
+    if !series_values.is_empty() {
+        let mut series_data = Vec::new();
+        for (i, &value) in series_values.iter().enumerate() {
+            let label = series_labels.get(i).cloned();
+            let color = colors.as_ref().and_then(|c| c.get(i).cloned());
+            series_data.push(DataPoint {
+                value,
+                label,
+                color,
+                x: series_x.get(i).copied(),
+                size: series_sizes.get(i).copied().flatten(),
+                ohlc: series_ohlc.get(i).copied(),
+                volume: series_volume.get(i).copied(),
+            });
+        }
+        series.push(models::Series {
+            name: None,
+            data: series_data,
+            color: colors.as_ref().and_then(|c| c.first().cloned()),
+            summary: None,
+        });
+    }
+
+    let request = GraphRequest {
+        graph_type,
+        series,
+        data,
+        title,
+        x_label,
+        y_label,
+        colors,
+        format,
+        width,
+        height,
+        scale,
+        x_scale,
+        y_scale,
+        stacking,
+        orientation,
+        legend,
+        theme,
+        palette,
+        matrix,
+        bins,
+        density,
+        show_mean,
+    };
+    if !lenient {
+        check_canvas(&request)?;
+        check_bin_width(&request, "bin_width")?;
+    }
+    Ok(request)
+}
+
+fn parse_number(field: &str, value: &str) -> Result<f64, ChartError> {
+    match value.trim().parse::<f64>() {
+        Ok(v) if v.is_finite() => Ok(v),
+        _ => Err(ChartError::invalid_value(
+            field,
+            value,
+            format!("Invalid {}: expected a number", field),
+        )),
+    }
+}
+
+fn parse_choice<T: Copy>(field: &str, value: &str, choices: &[(&str, T)]) -> Result<T, ChartError> {
+    match choices.iter().find(|(name, _)| *name == value) {
+        Some(&(_, choice)) => Ok(choice),
+        None => {
+            let names: Vec<&str> = choices.iter().map(|(name, _)| *name).collect();
+            Err(ChartError::invalid_value(
+                field,
+                value,
+                format!(
+                    "Unsupported {} (expected one of {})",
+                    field,
+                    names.join(", ")
+                ),
+            ))
+        }
+    }
+}
+
+fn parse_scale_type(field: &str, value: &str) -> Result<ScaleType, ChartError> {
+    parse_choice(
+        field,
+        value,
+        &[
+            ("linear", ScaleType::Linear),
+            ("log", ScaleType::Log),
+            ("time", ScaleType::Time),
+        ],
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn field(result: Result<GraphRequest, ChartError>) -> String {
+        match result {
+            Err(ChartError::InvalidValue { field, .. }) => field,
+            other => panic!("expected a field error, got {other:?}"),
+        }
+    }
+
+    #[test]
+    fn bins_must_be_positive() {
+        for body in [
+            r#"{"graph_type": "histogram", "bins": 0, "data": [1, 2]}"#,
+            r#"{"graph_type": "histogram", "bins": {"width": -1}, "data": [1, 2]}"#,
+            r#"{"graph_type": "histogram", "bins": {"width": 0}, "data": [1, 2]}"#,
+        ] {
+            assert_eq!(field(parse_json(body.as_bytes())), "bins", "{body}");
+        }
+        assert_eq!(field(parse_query(&[("bins", "0")])), "bins");
+        assert_eq!(field(parse_query(&[("bin_width", "-2")])), "bin_width");
+
+        // Lenient mode falls back to the default rule
+        let request = parse_json(br#"{"lenient": true, "bins": 0, "data": [1, 2]}"#).unwrap();
+        assert_eq!(request.bins, None);
+        let request = parse_query(&[("lenient", "true"), ("bin_width", "-2")]).unwrap();
+        assert_eq!(request.bins, None);
+    }
+
+    #[test]
+    fn negative_volume_is_rejected_unless_lenient() {
+        let body = br#"{"graph_type": "candlestick", "series": [{"data": [
+            {"open": 1, "high": 3, "low": 0, "close": 2, "volume": -5}
+        ]}]}"#;
+        assert_eq!(field(parse_json(body)), "series[0].data[0]");
+        let query = [
+            ("type", "candlestick"),
+            ("ohlc", "1:3:0:2"),
+            ("volume", "-5"),
+        ];
+        assert_eq!(field(parse_query(&query)), "volume");
+
+        let request = parse_query(&[("lenient", "true"), query[0], query[1], query[2]]).unwrap();
+        assert_eq!(request.series[0].data[0].volume, Some(-5.0));
+    }
+
+    #[test]
+    fn bin_width_must_fit_the_data_unless_lenient() {
+        let body = br#"{"graph_type": "histogram", "bins": {"width": 1}, "data": [0, 1000]}"#;
+        assert_eq!(field(parse_json(body)), "bins.width");
+        let query = [
+            ("type", "histogram"),
+            ("data", "0,1000"),
+            ("bin_width", "1"),
+        ];
+        assert_eq!(field(parse_query(&query)), "bin_width");
+        // 200 bins are fine, as are narrow widths on other chart types
+        assert!(parse_query(&[query[0], query[1], ("bin_width", "5")]).is_ok());
+        assert!(parse_query(&query[1..]).is_ok());
+
+        let body = br#"{"lenient": true, "graph_type": "histogram", "bins": {"width": 1}, "data": [0, 1000]}"#;
+        assert!(parse_json(body).is_ok());
+    }
+
+    #[test]
+    fn canvas_out_of_range_is_rejected_unless_lenient() {
+        assert_eq!(field(parse_query(&[("width", "-5")])), "width");
+        assert_eq!(field(parse_query(&[("height", "100000")])), "height");
+        assert_eq!(field(parse_query(&[("scale", "0")])), "scale");
+        // 4000x4000 leaves room for one device pixel per point
+        assert_eq!(
+            field(parse_query(&[
+                ("width", "4000"),
+                ("height", "4000"),
+                ("scale", "2")
+            ])),
+            "scale"
+        );
+        assert_eq!(
+            field(parse_json(br#"{"width": 100000, "data": [1]}"#)),
+            "width"
+        );
+        assert!(parse_query(&[("width", "4000"), ("scale", "1.5")]).is_ok());
+
+        let request = parse_query(&[("lenient", "true"), ("width", "-5")]).unwrap();
+        assert_eq!(request.width, Some(-5.0));
+        assert_eq!(
+            crate::utils::layout::Layout::from_request(&request).width,
+            MIN_SIZE
+        );
+        assert!(parse_json(br#"{"lenient": true, "scale": 9, "data": [1]}"#).is_ok());
+    }
+}
@@ -0,0 +1,156 @@
+use super::theme::Theme;
+use crate::models::{GraphRequest, GraphType};
+
+// Color values from requests end up inside SVG attributes, so only a small
+// whitelist of CSS color syntaxes is accepted:
+//   #rgb, #rgba, #rrggbb, #rrggbbaa
+//   named colors (letters only, e.g. "red", "steelblue")
+//   rgb()/rgba()/hsl()/hsla() with numeric arguments
+// Anything else is treated as if no color had been given.
+pub fn sanitize(color: &str) -> Option<&str> {
+    let color = color.trim();
+    if is_hex(color) || is_named(color) || is_functional(color) {
+        Some(color)
+    } else {
+        None
+    }
+}
+
+// Convenience for the optional color fields on the models
+pub fn sanitize_opt(color: Option<&String>) -> Option<&str> {
+    color.and_then(|c| sanitize(c))
+}
+
+// The one place that decides which color a series or point is drawn in:
+//   1. the point's own color (for charts that color points individually)
+//   2. the series' color
+//   3. request.colors, by series index
+//   4. the theme (or request) palette, by series index
+// Pie and donut slices are the categories, so they skip the series color and index
+// colors and palette by slice instead. The legend uses the same resolution.
+pub fn resolve<'a>(
+    theme: &'a Theme,
+    request: &'a GraphRequest,
+    series_idx: usize,
+    point_idx: Option<usize>,
+) -> &'a str {
+    let by_slice = matches!(request.graph_type, GraphType::Pie | GraphType::Donut);
+    let series = request.series.get(series_idx);
+    let point = point_idx.and_then(|i| series?.data.get(i));
+    let index = match point_idx {
+        Some(i) if by_slice => i,
+        _ => series_idx,
+    };
+
+    point
+        .and_then(|p| sanitize_opt(p.color.as_ref()))
+        .or_else(|| {
+            series
+                .filter(|_| !by_slice)
+                .and_then(|s| sanitize_opt(s.color.as_ref()))
+        })
+        .or_else(|| {
+            request
+                .colors
+                .as_ref()
+                .and_then(|colors| sanitize_opt(colors.get(index)))
+        })
+        .unwrap_or_else(|| theme.color(index))
+}
+
+fn is_hex(color: &str) -> bool {
+    match color.strip_prefix('#') {
+        Some(digits) => {
+            matches!(digits.len(), 3 | 4 | 6 | 8) && digits.chars().all(|c| c.is_ascii_hexdigit())
+        }
+        None => false,
+    }
+}
+
+fn is_named(color: &str) -> bool {
+    !color.is_empty() && color.len() <= 32 && color.chars().all(|c| c.is_ascii_alphabetic())
+}
+
+fn is_functional(color: &str) -> bool {
+    let Some(args) = ["rgb(", "rgba(", "hsl(", "hsla("]
+        .iter()
+        .find_map(|prefix| color.strip_prefix(prefix))
+        .and_then(|rest| rest.strip_suffix(')'))
+    else {
+        return false;
+    };
+    args.chars()
+        .all(|c| c.is_ascii_digit() || matches!(c, ' ' | '.' | ',' | '%' | '+' | '-'))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::models::{DataPoint, Series};
+
+    #[test]
+    fn accepts_css_colors() {
+        for color in [
+            "#fff",
+            "#FFFA",
+            "#4E79A7",
+            "#4e79a780",
+            "red",
+            "steelblue",
+            "rgb(255, 0, 0)",
+            "rgba(0,0,0,0.5)",
+            "hsl(120, 50%, 50%)",
+            " #000000 ",
+        ] {
+            assert_eq!(sanitize(color), Some(color.trim()), "{color}");
+        }
+    }
+
+    #[test]
+    fn rejects_hostile_colors() {
+        for color in [
+            "",
+            "#12",
+            "#GGGGGG",
+            r#"red" onload="alert(1)"#,
+            r##"#000"/><script>alert(1)</script><rect fill="#000"##,
+            "url(#gradient)",
+            "rgb(1,2,3);stroke:red",
+            "rgb(1,2,3)>",
+            "red blue",
+            "&amp;",
+        ] {
+            assert_eq!(sanitize(color), None, "{color}");
+        }
+    }
+
+    #[test]
+    fn resolves_point_then_series_then_request_then_theme() {
+        let request = GraphRequest {
+            series: vec![
+                Series::new("a")
+                    .color("#111111")
+                    .data([DataPoint::new(1.0).color("#222222"), DataPoint::new(2.0)]),
+                Series::new("b").data([1.0, 2.0]),
+                Series::new("c").color("not a color").data([1.0]),
+            ],
+            colors: Some(vec!["#333333".to_string(), "#444444".to_string()]),
+            ..GraphRequest::default()
+        };
+        let theme = Theme::from_request(&request);
+
+        assert_eq!(resolve(&theme, &request, 0, Some(0)), "#222222");
+        assert_eq!(resolve(&theme, &request, 0, Some(1)), "#111111");
+        assert_eq!(resolve(&theme, &request, 0, None), "#111111");
+        assert_eq!(resolve(&theme, &request, 1, Some(0)), "#444444");
+        assert_eq!(resolve(&theme, &request, 2, None), theme.color(2));
+
+        // Slices ignore the series color and take request.colors by slice
+        let pie = GraphRequest {
+            graph_type: GraphType::Pie,
+            ..request
+        };
+        assert_eq!(resolve(&theme, &pie, 0, Some(0)), "#222222");
+        assert_eq!(resolve(&theme, &pie, 0, Some(1)), "#444444");
+    }
+}
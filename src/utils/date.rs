@@ -0,0 +1,119 @@
+// Proleptic Gregorian calendar helpers (UTC), based on Howard Hinnant's
+// days_from_civil / civil_from_days algorithms.
+
+pub const SECONDS_PER_DAY: i64 = 86_400;
+
+// Unix timestamps of 0000-01-01 and 9999-12-31 23:59:59, the range dates are drawn in
+pub const MIN_TIMESTAMP: f64 = -62_167_219_200.0;
+pub const MAX_TIMESTAMP: f64 = 253_402_300_799.0;
+
+// Days since 1970-01-01 for the given civil date
+pub fn days_from_civil(year: i64, month: u32, day: u32) -> i64 {
+    let y = if month <= 2 { year - 1 } else { year };
+    let era = y.div_euclid(400);
+    let yoe = y - era * 400;
+    let m = month as i64;
+    let doy = (153 * (if m > 2 { m - 3 } else { m + 9 }) + 2) / 5 + day as i64 - 1;
+    let doe = yoe * 365 + yoe / 4 - yoe / 100 + doy;
+    era * 146_097 + doe - 719_468
+}
+
+// Civil date (year, month, day) for days since 1970-01-01
+pub fn civil_from_days(days: i64) -> (i64, u32, u32) {
+    let z = days + 719_468;
+    let era = z.div_euclid(146_097);
+    let doe = z - era * 146_097;
+    let yoe = (doe - doe / 1460 + doe / 36_524 - doe / 146_096) / 365;
+    let doy = doe - (365 * yoe + yoe / 4 - yoe / 100);
+    let mp = (5 * doy + 2) / 153;
+    let day = (doy - (153 * mp + 2) / 5 + 1) as u32;
+    let month = if mp < 10 { mp + 3 } else { mp - 9 } as u32;
+    let year = yoe + era * 400 + if month <= 2 { 1 } else { 0 };
+    (year, month, day)
+}
+
+pub const MONTH_NAMES: [&str; 12] = [
+    "Jan", "Feb", "Mar", "Apr", "May", "Jun", "Jul", "Aug", "Sep", "Oct", "Nov", "Dec",
+];
+
+fn days_in_month(year: i64, month: u32) -> u32 {
+    match month {
+        2 if year % 4 == 0 && (year % 100 != 0 || year % 400 == 0) => 29,
+        2 => 28,
+        4 | 6 | 9 | 11 => 30,
+        _ => 31,
+    }
+}
+
+// Days since 1970-01-01 for "YYYY-MM-DD"; a time of day after it ("T..." or " ...")
+// is ignored
+pub fn parse_iso_date(text: &str) -> Option<i64> {
+    let text = text.trim();
+    let date = match text.find(['T', ' ']) {
+        Some(i) => &text[..i],
+        None => text,
+    };
+    let mut parts = date.splitn(3, '-');
+    let (year, month, day) = (parts.next()?, parts.next()?, parts.next()?);
+    if year.len() != 4 || month.len() != 2 || day.len() != 2 {
+        return None;
+    }
+    let year: i64 = year.parse().ok()?;
+    let month: u32 = month.parse().ok()?;
+    let day: u32 = day.parse().ok()?;
+    if !(1..=12).contains(&month) || day == 0 || day > days_in_month(year, month) {
+        return None;
+    }
+    Some(days_from_civil(year, month, day))
+}
+
+// Days since 1970-01-01 of a Unix timestamp in seconds; None outside years 0 to 9999
+pub fn day_from_timestamp(timestamp: f64) -> Option<i64> {
+    (MIN_TIMESTAMP..=MAX_TIMESTAMP)
+        .contains(&timestamp)
+        .then(|| (timestamp / SECONDS_PER_DAY as f64).floor() as i64)
+}
+
+// 0 = Sunday, .., 6 = Saturday; 1970-01-01 was a Thursday
+pub fn weekday(days: i64) -> u32 {
+    (days + 4).rem_euclid(7) as u32
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parses_iso_dates() {
+        assert_eq!(parse_iso_date("1970-01-01"), Some(0));
+        assert_eq!(parse_iso_date("2024-02-29"), Some(19_782));
+        assert_eq!(parse_iso_date("2024-02-29T09:30:00Z"), Some(19_782));
+        assert_eq!(parse_iso_date("2023-02-29"), None);
+        assert_eq!(parse_iso_date("2024-13-01"), None);
+        assert_eq!(parse_iso_date("2024-1-5"), None);
+        assert_eq!(parse_iso_date("Mon"), None);
+    }
+
+    #[test]
+    fn timestamps_outside_the_calendar_have_no_day() {
+        assert_eq!(day_from_timestamp(86_399.0), Some(0));
+        assert_eq!(day_from_timestamp(-1.0), Some(-1));
+        assert_eq!(
+            day_from_timestamp(MIN_TIMESTAMP),
+            parse_iso_date("0000-01-01")
+        );
+        assert_eq!(
+            day_from_timestamp(MAX_TIMESTAMP),
+            parse_iso_date("9999-12-31")
+        );
+        assert_eq!(day_from_timestamp(1e300), None);
+        assert_eq!(day_from_timestamp(-1e300), None);
+    }
+
+    #[test]
+    fn weekdays_start_on_sunday() {
+        assert_eq!(weekday(0), 4);
+        assert_eq!(weekday(parse_iso_date("2024-06-02").unwrap()), 0);
+        assert_eq!(weekday(parse_iso_date("1969-12-31").unwrap()), 3);
+    }
+}
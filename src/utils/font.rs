@@ -0,0 +1,52 @@
+use resvg::usvg::{self, TreeParsing};
+use std::sync::OnceLock;
+use usvg_text_layout::TreeTextToPath;
+
+pub const FONT_FAMILY: &str = "M PLUS 1p";
+
+// フォントデータをバイナリとして直接埋め込み
+static FONT_DATA: &[u8] = include_bytes!("../../assets/MPLUS1p-Regular.ttf");
+
+pub fn load_font_database() -> fontdb::Database {
+    let mut fontdb = fontdb::Database::new();
+    fontdb.load_font_data(FONT_DATA.to_vec());
+
+    // 読み込まれたフォントの情報をログ出力（console_log!はWorkers上でしか動かない）
+    #[cfg(all(feature = "worker", target_arch = "wasm32"))]
+    for face in fontdb.faces() {
+        if let Some(families) = face.families.first() {
+            worker::console_log!("Loaded font family: {}", families.0);
+        }
+    }
+
+    fontdb
+}
+
+// The same embedded face, parsed once, for measuring text before it is rendered
+pub fn face() -> &'static ttf_parser::Face<'static> {
+    static FACE: OnceLock<ttf_parser::Face<'static>> = OnceLock::new();
+    FACE.get_or_init(|| ttf_parser::Face::parse(FONT_DATA, 0).expect("embedded font is valid"))
+}
+
+// Parse the SVG string and convert every text node to paths using the embedded font,
+// so the resulting tree renders identically without the font being installed.
+pub fn parse_svg_tree(svg_str: &str) -> Result<usvg::Tree, String> {
+    let fontdb = load_font_database();
+
+    // SVGパース用のオプション設定
+    let opt = usvg::Options {
+        font_family: FONT_FAMILY.to_string(),
+        font_size: 12.0,
+        dpi: 96.0,
+        ..usvg::Options::default()
+    };
+
+    // SVGをパース
+    let mut tree =
+        usvg::Tree::from_str(svg_str, &opt).map_err(|e| format!("Failed to parse SVG: {}", e))?;
+
+    // テキストをパスに変換
+    tree.convert_text(&fontdb);
+
+    Ok(tree)
+}
@@ -0,0 +1,137 @@
+use crate::models::GraphRequest;
+
+pub const DEFAULT_WIDTH: f64 = 800.0;
+pub const DEFAULT_HEIGHT: f64 = 600.0;
+pub const MIN_SIZE: f64 = 200.0;
+pub const MAX_SIZE: f64 = 4000.0;
+pub const MAX_SCALE: f64 = 4.0;
+// Upper bound for the rasterized pixmap (width * height * scale^2), ~64MB of RGBA
+pub const MAX_PIXELS: f64 = 16_000_000.0;
+
+// Canvas size and plot rectangle shared by every chart.
+//
+// Cartesian charts draw inside a group translated by (origin_x, origin_y); within that
+// group the plot spans x = 0..plot_width and y = plot_top..plot_bottom, leaving room for
+// the title above and tick labels / axis label below.
+//
+// The free_* fields bound the part of the canvas (in canvas coordinates) that is not
+// taken by a legend; charts without axes center themselves in it.
+#[derive(Clone, Copy)]
+pub struct Layout {
+    pub width: f64,
+    pub height: f64,
+    pub scale: f64,
+    pub origin_x: f64,
+    pub origin_y: f64,
+    pub plot_width: f64,
+    pub plot_top: f64,
+    pub plot_bottom: f64,
+    pub free_top: f64,
+    pub free_right: f64,
+    pub free_bottom: f64,
+}
+
+impl Layout {
+    // Out-of-range sizes and scales only get here in lenient mode; they are clamped
+    pub fn from_request(request: &GraphRequest) -> Self {
+        let width = request
+            .width
+            .map_or(DEFAULT_WIDTH, |w| w.clamp(MIN_SIZE, MAX_SIZE));
+        let height = request
+            .height
+            .map_or(DEFAULT_HEIGHT, |h| h.clamp(MIN_SIZE, MAX_SIZE));
+        let scale = request
+            .scale
+            .filter(|s| s.is_finite() && *s > 0.0)
+            .map_or(1.0, |s| s.min(MAX_SCALE))
+            .min((MAX_PIXELS / (width * height)).sqrt());
+        Self::new(width, height, scale)
+    }
+
+    pub fn new(width: f64, height: f64, scale: f64) -> Self {
+        // Margins shrink with the canvas so small thumbnails keep a usable plot area
+        let k = (width / DEFAULT_WIDTH)
+            .min(height / DEFAULT_HEIGHT)
+            .clamp(0.75, 1.0);
+        Layout {
+            width,
+            height,
+            scale,
+            origin_x: 80.0 * k,
+            origin_y: 50.0 * k,
+            plot_width: width - 160.0 * k,
+            plot_top: 50.0 * k,
+            plot_bottom: height - 150.0 * k,
+            free_top: 0.0,
+            free_right: width,
+            free_bottom: height,
+        }
+    }
+
+    // Same canvas with a wider left margin, e.g. for long category labels
+    pub fn with_left_margin(&self, margin: f64) -> Self {
+        let right_margin = self.right_margin();
+        Layout {
+            origin_x: margin,
+            plot_width: self.width - margin - right_margin,
+            ..*self
+        }
+    }
+
+    // Room for title lines after the first
+    pub fn with_title_height(&self, extra: f64) -> Self {
+        Layout {
+            plot_top: self.plot_top + extra,
+            free_top: self.free_top + extra,
+            ..*self
+        }
+    }
+
+    // Same canvas with a wider right margin, e.g. for a legend
+    pub fn with_right_margin(&self, margin: f64) -> Self {
+        Layout {
+            plot_width: self.width - self.origin_x - margin,
+            ..*self
+        }
+    }
+
+    pub fn right_margin(&self) -> f64 {
+        self.width - self.origin_x - self.plot_width
+    }
+
+    // Space below the plot for tick labels and the axis label
+    pub fn bottom_margin(&self) -> f64 {
+        self.free_bottom - self.origin_y - self.plot_bottom
+    }
+
+    // Center of the free area, for charts without axes
+    pub fn free_center(&self) -> (f64, f64) {
+        (
+            self.free_right / 2.0,
+            (self.free_top + self.free_bottom) / 2.0,
+        )
+    }
+
+    // Largest radius that keeps a circle plus `padding` inside the free area
+    pub fn free_radius(&self, padding: f64) -> f64 {
+        ((self.free_right).min(self.free_bottom - self.free_top) / 2.0 - padding).max(10.0)
+    }
+
+    pub fn center_x(&self) -> f64 {
+        self.plot_width / 2.0
+    }
+
+    // Margin scale factor relative to the default 800x600 canvas
+    pub fn margin_ratio(&self) -> f64 {
+        self.origin_y / 50.0
+    }
+
+    // Top-left corner of the legend, in plot group coordinates
+    pub fn legend_x(&self) -> f64 {
+        self.plot_width + 20.0 * self.margin_ratio()
+    }
+
+    pub fn legend_y(&self) -> f64 {
+        self.plot_top
+    }
+}
@@ -0,0 +1,384 @@
+use super::color;
+use super::layout::Layout;
+use super::svg::escape_text;
+use super::text;
+use super::theme::Theme;
+use crate::models::{GraphRequest, GraphType, LegendPosition};
+
+// Sizes at the default 800x600 canvas; gaps shrink with the margins
+const GAP: f64 = 10.0;
+const SYMBOL_WIDTH: f64 = 20.0;
+const SYMBOL_GAP: f64 = 6.0;
+const ITEM_GAP: f64 = 16.0;
+// Share of the canvas width a label may take before it is shortened with an ellipsis
+const MAX_LABEL_SHARE_SIDE: f64 = 0.2;
+const MAX_LABEL_SHARE_ROW: f64 = 0.4;
+// A side legend never takes more than this share of the canvas width
+const MAX_SIDE_SHARE: f64 = 0.45;
+// Labels are never shortened below this many font sizes
+const MIN_LABEL_EMS: f64 = 3.0;
+// Legends above or below the plot leave it at least this share of its height
+const MIN_PLOT_SHARE: f64 = 0.4;
+
+// Drawn next to each entry, matching how the series itself is drawn
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Symbol {
+    Rect,
+    Line,
+    Circle,
+}
+
+impl Symbol {
+    fn for_chart(graph_type: GraphType) -> Self {
+        match graph_type {
+            GraphType::Line | GraphType::Radar => Symbol::Line,
+            GraphType::Scatter | GraphType::Bubble => Symbol::Circle,
+            _ => Symbol::Rect,
+        }
+    }
+}
+
+struct Item {
+    label: String,
+    color: String,
+}
+
+// Series names (slice labels for pie charts) with their colors, measured and arranged
+// in a grid: a side legend fills columns top to bottom and adds columns when the plot
+// is not tall enough, legends above or below the plot fill rows left to right.
+pub struct Legend {
+    pub position: LegendPosition,
+    symbol: Symbol,
+    items: Vec<Item>,
+    font_size: f64,
+    line_width: f64,
+    gap: f64,
+    row_height: f64,
+    column_width: f64,
+    columns: usize,
+    rows: usize,
+    // Height taken from the plot by a legend above or below it
+    inset: f64,
+    pub width: f64,
+    pub height: f64,
+}
+
+// Entries for every series once there is more than one or any of them is named;
+// unnamed series are numbered. Pie charts list their labelled slices instead.
+fn items(request: &GraphRequest, theme: &Theme) -> Vec<Item> {
+    match request.graph_type {
+        GraphType::Pie | GraphType::Donut => {
+            let Some(series) = request.series.first() else {
+                return Vec::new();
+            };
+            if series.data.iter().all(|d| d.label.is_none()) {
+                return Vec::new();
+            }
+            series
+                .data
+                .iter()
+                .enumerate()
+                .filter(|(_, d)| d.value > 0.0)
+                .map(|(i, d)| Item {
+                    label: d.label.clone().unwrap_or_else(|| (i + 1).to_string()),
+                    color: color::resolve(theme, request, 0, Some(i)).to_string(),
+                })
+                .collect()
+        }
+        // Rows and columns are labelled on the axes; the color scale explains the cells
+        GraphType::Heatmap | GraphType::Calendar => Vec::new(),
+        // Each box is named below the axis; candles are colored by direction
+        GraphType::Box | GraphType::Candlestick | GraphType::Ohlc => Vec::new(),
+        _ => {
+            if request.series.len() < 2 && request.series.iter().all(|s| s.name.is_none()) {
+                return Vec::new();
+            }
+            request
+                .series
+                .iter()
+                .enumerate()
+                .map(|(i, s)| Item {
+                    label: s
+                        .name
+                        .clone()
+                        .unwrap_or_else(|| format!("Series {}", i + 1)),
+                    color: color::resolve(theme, request, i, None).to_string(),
+                })
+                .collect()
+        }
+    }
+}
+
+impl Legend {
+    pub fn new(request: &GraphRequest, theme: &Theme, layout: &Layout) -> Self {
+        let position = request.legend;
+        let mut items = if position == LegendPosition::None {
+            Vec::new()
+        } else {
+            items(request, theme)
+        };
+
+        let k = layout.margin_ratio();
+        let font_size = theme.tick_font_size;
+        let row_height = font_size + 10.0 * k;
+        let plot_height = layout.plot_bottom - layout.plot_top;
+        let decoration = SYMBOL_WIDTH + SYMBOL_GAP + ITEM_GAP;
+        let side_width = layout.width * MAX_SIDE_SHARE + ITEM_GAP;
+
+        // A side legend that needs several columns shortens its labels to fit them,
+        // down to a few characters; whatever still does not fit is summarized
+        let rows_fit = ((plot_height / row_height).floor() as usize).max(1);
+        let max_label = match position {
+            LegendPosition::Right => {
+                let needed = items.len().div_ceil(rows_fit).max(1);
+                (layout.width * MAX_LABEL_SHARE_SIDE)
+                    .min(side_width / needed as f64 - decoration)
+                    .max(font_size * MIN_LABEL_EMS)
+            }
+            _ => layout.width * MAX_LABEL_SHARE_ROW,
+        };
+        for item in &mut items {
+            item.label = text::truncate_to_width(&item.label, max_label, font_size);
+        }
+        let label_width = items
+            .iter()
+            .map(|item| text::text_width(&item.label, font_size))
+            .fold(0.0, f64::max);
+        let mut column_width = decoration + label_width;
+
+        let (columns, max_rows) = match position {
+            LegendPosition::Right => {
+                let max_columns = ((side_width / column_width).floor() as usize).max(1);
+                (
+                    items.len().div_ceil(rows_fit).clamp(1, max_columns),
+                    rows_fit,
+                )
+            }
+            _ => {
+                let available = layout.width - 4.0 * GAP * k;
+                let columns = (((available + ITEM_GAP) / column_width).floor() as usize)
+                    .clamp(1, items.len().max(1));
+                let max_height = plot_height * (1.0 - MIN_PLOT_SHARE) - GAP * k;
+                (columns, ((max_height / row_height).floor() as usize).max(1))
+            }
+        };
+        let capacity = columns * max_rows;
+        if items.len() > capacity {
+            let hidden = items.len() - capacity + 1;
+            items.truncate(capacity - 1);
+            let label = format!("+{hidden} more");
+            column_width = column_width.max(decoration + text::text_width(&label, font_size));
+            items.push(Item {
+                label,
+                color: String::new(),
+            });
+        }
+        let rows = items.len().max(1).div_ceil(columns);
+
+        let (width, height) = if items.is_empty() {
+            (0.0, 0.0)
+        } else {
+            (
+                columns as f64 * column_width - ITEM_GAP,
+                rows as f64 * row_height,
+            )
+        };
+        let inset = (height + GAP * k).min(plot_height * (1.0 - MIN_PLOT_SHARE));
+
+        Legend {
+            position,
+            symbol: Symbol::for_chart(request.graph_type),
+            items,
+            font_size,
+            line_width: theme.line_width,
+            gap: GAP * k,
+            row_height,
+            column_width,
+            columns,
+            rows,
+            inset,
+            width,
+            height,
+        }
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.items.is_empty()
+    }
+
+    // Shrinks the plot (and the free area) so the legend fits beside it
+    pub fn reserve(&self, layout: Layout) -> Layout {
+        if self.is_empty() {
+            return layout;
+        }
+        match self.position {
+            LegendPosition::Right => {
+                let margin = layout.right_margin().max(self.width + 3.0 * self.gap);
+                Layout {
+                    free_right: layout.width - self.width - 2.0 * self.gap,
+                    ..layout.with_right_margin(margin)
+                }
+            }
+            LegendPosition::Top => Layout {
+                plot_top: layout.plot_top + self.inset,
+                free_top: layout.origin_y + layout.plot_top + self.inset,
+                ..layout
+            },
+            LegendPosition::Bottom => Layout {
+                plot_bottom: layout.plot_bottom - self.inset,
+                free_bottom: layout.height - self.inset - self.gap,
+                ..layout
+            },
+            LegendPosition::None => layout,
+        }
+    }
+
+    // Top-left corner in canvas coordinates, given the layout returned by reserve
+    fn origin(&self, layout: &Layout) -> (f64, f64) {
+        let centered = (layout.width - self.width) / 2.0;
+        match self.position {
+            LegendPosition::Right => (
+                layout.origin_x + layout.plot_width + 2.0 * self.gap,
+                layout.origin_y + layout.plot_top,
+            ),
+            LegendPosition::Top => (centered, layout.origin_y + layout.plot_top - self.inset),
+            LegendPosition::Bottom | LegendPosition::None => {
+                (centered, layout.height - self.height - self.gap)
+            }
+        }
+    }
+
+    // `translate` is the offset of the group the legend is drawn into
+    pub fn render(&self, layout: &Layout, translate: (f64, f64)) -> String {
+        if self.is_empty() {
+            return String::new();
+        }
+        let (x, y) = self.origin(layout);
+        let mut svg = format!(
+            r#"<g transform="translate({}, {})">"#,
+            x - translate.0,
+            y - translate.1
+        );
+
+        for (i, item) in self.items.iter().enumerate() {
+            let (row, column) = match self.position {
+                LegendPosition::Right => (i % self.rows, i / self.rows),
+                _ => (i / self.columns, i % self.columns),
+            };
+            let left = column as f64 * self.column_width;
+            let middle = row as f64 * self.row_height + self.row_height / 2.0;
+            // The "+N more" entry has no color and no symbol
+            if !item.color.is_empty() {
+                svg.push_str(&self.symbol(left, middle, &item.color));
+            }
+            svg.push_str(&format!(
+                r#"<text x="{}" y="{}" font-family="M PLUS 1p" font-size="{}">{}</text>"#,
+                left + SYMBOL_WIDTH + SYMBOL_GAP,
+                middle + self.font_size / 3.0,
+                self.font_size,
+                escape_text(&item.label)
+            ));
+        }
+
+        svg.push_str("</g>");
+        svg
+    }
+
+    fn symbol(&self, left: f64, middle: f64, color: &str) -> String {
+        let size = self.font_size;
+        match self.symbol {
+            Symbol::Rect => format!(
+                r#"<rect x="{}" y="{}" width="{}" height="{}" fill="{}" />"#,
+                left + (SYMBOL_WIDTH - size) / 2.0,
+                middle - size / 2.0,
+                size,
+                size,
+                color
+            ),
+            Symbol::Line => format!(
+                r#"<line x1="{}" y1="{middle}" x2="{}" y2="{middle}" stroke="{color}" stroke-width="{}" /><circle cx="{}" cy="{middle}" r="3.5" fill="{color}" />"#,
+                left,
+                left + SYMBOL_WIDTH,
+                self.line_width,
+                left + SYMBOL_WIDTH / 2.0,
+            ),
+            Symbol::Circle => format!(
+                r#"<circle cx="{}" cy="{}" r="{}" fill="{}" />"#,
+                left + SYMBOL_WIDTH / 2.0,
+                middle,
+                size / 2.0,
+                color
+            ),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::models::Series;
+
+    fn request(position: LegendPosition, series: usize) -> GraphRequest {
+        GraphRequest {
+            graph_type: GraphType::Line,
+            legend: position,
+            series: (0..series)
+                .map(|i| Series::new(format!("Sensor {i} in building A")).data([1.0, 2.0]))
+                .collect(),
+            ..GraphRequest::default()
+        }
+    }
+
+    fn legend(request: &GraphRequest) -> (Legend, Layout) {
+        let theme = Theme::from_request(request);
+        let layout = Layout::from_request(request);
+        let legend = Legend::new(request, &theme, &layout);
+        let reserved = legend.reserve(layout);
+        (legend, reserved)
+    }
+
+    #[test]
+    fn single_unnamed_series_has_no_legend() {
+        let request = GraphRequest {
+            series: vec![Series::default().data([1.0, 2.0])],
+            ..GraphRequest::default()
+        };
+        let (legend, _) = legend(&request);
+        assert!(legend.is_empty());
+        assert!(legend
+            .render(&Layout::from_request(&request), (0.0, 0.0))
+            .is_empty());
+    }
+
+    #[test]
+    fn side_legend_wraps_into_columns_and_shrinks_the_plot() {
+        let (legend, layout) = legend(&request(LegendPosition::Right, 30));
+        assert!(legend.columns > 1);
+        assert_eq!(legend.items.len(), 30);
+        assert!(legend.width <= layout.width * MAX_SIDE_SHARE);
+        assert!(layout.origin_x + layout.plot_width + legend.width < layout.width);
+    }
+
+    #[test]
+    fn overflowing_entries_are_summarized() {
+        let (legend, layout) = legend(&request(LegendPosition::Top, 200));
+        let last = legend.items.last().unwrap();
+        assert!(last.label.starts_with('+') && last.label.ends_with(" more"));
+        assert!(legend.items.len() < 200);
+        // The plot keeps its minimum share of the height
+        let full = Layout::from_request(&request(LegendPosition::Top, 200));
+        assert!(
+            layout.plot_bottom - layout.plot_top
+                >= (full.plot_bottom - full.plot_top) * MIN_PLOT_SHARE
+        );
+    }
+
+    #[test]
+    fn hidden_legend_leaves_the_layout_alone() {
+        let (legend, layout) = legend(&request(LegendPosition::None, 3));
+        let full = Layout::from_request(&request(LegendPosition::None, 3));
+        assert!(legend.is_empty());
+        assert_eq!(layout.plot_width, full.plot_width);
+        assert_eq!(layout.plot_top, full.plot_top);
+    }
+}
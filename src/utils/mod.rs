@@ -1,11 +1,14 @@
+pub mod color;
+pub mod date;
+pub mod font;
+pub mod layout;
+pub mod legend;
+pub mod palette;
 pub mod png;
+pub mod scale;
 pub mod svg;
-
-pub fn get_default_colors() -> Vec<&'static str> {
-    vec![
-        "#0000FF", "#FFB3B3", "#B3E0FF", "#FFE6B3", "#B3FFB3", "#E6B3FF", "#FFD9B3",
-    ]
-}
+pub mod text;
+pub mod theme;
 
 pub fn format_number(num: f64) -> String {
     if num.fract() == 0.0 {
@@ -0,0 +1,138 @@
+use crate::models::PaletteName;
+
+// Categorical palettes give every series a distinct hue; sequential and diverging
+// ones are ramps for ordered values, interpolated between their stops.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Kind {
+    Categorical,
+    Sequential,
+    Diverging,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Palette {
+    pub kind: Kind,
+    pub stops: &'static [&'static str],
+}
+
+// Tableau 10 (Tableau's default categorical palette since 2016)
+pub const TABLEAU10: &[&str] = &[
+    "#4E79A7", "#F28E2B", "#E15759", "#76B7B2", "#59A14F", "#EDC948", "#B07AA1", "#FF9DA7",
+    "#9C755F", "#BAB0AC",
+];
+
+// Okabe & Ito (2008), distinguishable with all common forms of color blindness
+pub const OKABE_ITO: &[&str] = &[
+    "#E69F00", "#56B4E9", "#009E73", "#F0E442", "#0072B2", "#D55E00", "#CC79A7", "#000000",
+];
+
+// matplotlib's viridis, perceptually uniform from dark purple to yellow
+pub const VIRIDIS: &[&str] = &[
+    "#440154", "#482878", "#3E4A89", "#31688E", "#26828E", "#1F9E89", "#35B779", "#6DCD59",
+    "#B4DE2C", "#FDE725",
+];
+
+// ColorBrewer RdBu (11 classes), red through white to blue
+pub const RDBU: &[&str] = &[
+    "#67001F", "#B2182B", "#D6604D", "#F4A582", "#FDDBC7", "#F7F7F7", "#D1E5F0", "#92C5DE",
+    "#4393C3", "#2166AC", "#053061",
+];
+
+impl Palette {
+    pub fn named(name: PaletteName) -> Self {
+        match name {
+            PaletteName::Tableau10 => Palette {
+                kind: Kind::Categorical,
+                stops: TABLEAU10,
+            },
+            PaletteName::OkabeIto => Palette {
+                kind: Kind::Categorical,
+                stops: OKABE_ITO,
+            },
+            PaletteName::Viridis => Palette {
+                kind: Kind::Sequential,
+                stops: VIRIDIS,
+            },
+            PaletteName::RdBu => Palette {
+                kind: Kind::Diverging,
+                stops: RDBU,
+            },
+        }
+    }
+
+    // Color at position t in 0..=1, blending linearly between neighbouring stops
+    pub fn at(&self, t: f64) -> String {
+        let t = if t.is_finite() {
+            t.clamp(0.0, 1.0)
+        } else {
+            0.0
+        };
+        let position = t * (self.stops.len() - 1) as f64;
+        let i = (position.floor() as usize).min(self.stops.len() - 2);
+        let (r1, g1, b1) = rgb(self.stops[i]);
+        let (r2, g2, b2) = rgb(self.stops[i + 1]);
+        let f = position - i as f64;
+        let mix = |a: u8, b: u8| (a as f64 + (b as f64 - a as f64) * f).round() as u8;
+        format!("#{:02X}{:02X}{:02X}", mix(r1, r2), mix(g1, g2), mix(b1, b2))
+    }
+
+    // Colors for n series: categorical palettes are used as they are (and wrap around
+    // when there are more series), ramps are sampled evenly from end to end
+    pub fn colors(&self, n: usize) -> Vec<String> {
+        match self.kind {
+            Kind::Categorical => self.stops.iter().map(|c| c.to_string()).collect(),
+            Kind::Sequential | Kind::Diverging if n <= 1 => vec![self.at(0.5)],
+            Kind::Sequential | Kind::Diverging => {
+                (0..n).map(|i| self.at(i as f64 / (n - 1) as f64)).collect()
+            }
+        }
+    }
+}
+
+// Black or white text, whichever reads better on a color returned by `at`
+pub fn text_on(background: &str) -> &'static str {
+    let (r, g, b) = rgb(background);
+    let luminance = 0.299 * r as f64 + 0.587 * g as f64 + 0.114 * b as f64;
+    if luminance > 140.0 {
+        "#000000"
+    } else {
+        "#FFFFFF"
+    }
+}
+
+// Stops are always #rrggbb
+fn rgb(hex: &str) -> (u8, u8, u8) {
+    let channel = |i: usize| u8::from_str_radix(&hex[i..i + 2], 16).unwrap_or(0);
+    (channel(1), channel(3), channel(5))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn interpolates_between_stops() {
+        let viridis = Palette::named(PaletteName::Viridis);
+        assert_eq!(viridis.at(0.0), "#440154");
+        assert_eq!(viridis.at(1.0), "#FDE725");
+        assert_eq!(viridis.at(2.0), "#FDE725");
+        assert_eq!(viridis.at(f64::NAN), "#440154");
+
+        // The diverging ramp is white in the middle
+        let rdbu = Palette::named(PaletteName::RdBu);
+        assert_eq!(rdbu.at(0.5), "#F7F7F7");
+        // A quarter of the way from the second to the third stop
+        assert_eq!(viridis.at(1.25 / 9.0), "#46317C");
+    }
+
+    #[test]
+    fn samples_ramps_evenly() {
+        let viridis = Palette::named(PaletteName::Viridis);
+        assert_eq!(viridis.colors(2), ["#440154", "#FDE725"]);
+        assert_eq!(viridis.colors(1), [viridis.at(0.5)]);
+        assert_eq!(viridis.colors(10), VIRIDIS);
+
+        let okabe_ito = Palette::named(PaletteName::OkabeIto);
+        assert_eq!(okabe_ito.colors(3), OKABE_ITO);
+    }
+}
@@ -0,0 +1,348 @@
+use super::date::{
+    civil_from_days, days_from_civil, MAX_TIMESTAMP, MIN_TIMESTAMP, SECONDS_PER_DAY,
+};
+use crate::models::ScaleType;
+
+const TARGET_TICKS: f64 = 5.0;
+// Rounding out to whole steps never gets near this; it only bounds hostile input
+const MAX_TICKS: usize = 100;
+
+// Maps a data domain onto a pixel range and provides "nice" tick values with
+// formatted labels. Time scales interpret values as Unix timestamps in seconds (UTC).
+pub struct Scale {
+    kind: ScaleType,
+    domain: (f64, f64),
+    range: (f64, f64),
+    ticks: Vec<f64>,
+    // Linear: tick step; Time: tick interval in seconds (months/years approximated)
+    step: f64,
+}
+
+impl Scale {
+    pub fn new(kind: ScaleType, min: f64, max: f64, range: (f64, f64)) -> Self {
+        let (min, max) = if min.is_finite() && max.is_finite() && min <= max {
+            (min, max)
+        } else {
+            (0.0, 0.0)
+        };
+        match kind {
+            ScaleType::Linear => Self::linear(min, max, range),
+            ScaleType::Log => Self::log(min, max, range),
+            ScaleType::Time => Self::time(min, max, range),
+        }
+    }
+
+    fn linear(min: f64, max: f64, range: (f64, f64)) -> Self {
+        let (min, max) = if min == max {
+            if min == 0.0 {
+                (0.0, 1.0)
+            } else {
+                (min - min.abs() * 0.5, max + max.abs() * 0.5)
+            }
+        } else {
+            (min, max)
+        };
+        let step = nice_step(split(min, max, TARGET_TICKS));
+        // Rounded out to whole steps, but never past the largest finite values
+        let lo = ((min / step).floor() * step).max(f64::MIN);
+        let hi = ((max / step).ceil() * step).min(f64::MAX);
+        let count = ((hi / step - lo / step).round() as usize).min(MAX_TICKS);
+        let ticks = (0..=count)
+            .map(|i| lo + step * i as f64)
+            .take_while(|&t| t <= hi)
+            .collect();
+        Scale {
+            kind: ScaleType::Linear,
+            domain: (lo, hi),
+            range,
+            ticks,
+            step,
+        }
+    }
+
+    fn log(min: f64, max: f64, range: (f64, f64)) -> Self {
+        // Non-positive values cannot be placed on a log axis; they sit on the floor
+        let max = if max > 0.0 { max } else { 1.0 };
+        let min = if min > 0.0 { min } else { max / 10.0 };
+        // Decades whose powers of ten are normal, finite floats
+        let lo = (min.log10().floor() as i32).clamp(f64::MIN_10_EXP, f64::MAX_10_EXP - 1);
+        let hi = (max.log10().ceil() as i32).clamp(lo + 1, f64::MAX_10_EXP);
+        let decades = hi - lo;
+        // Show 2x and 5x intermediates when the axis only spans a couple of decades
+        let multipliers: &[f64] = if decades <= 2 {
+            &[1.0, 2.0, 5.0]
+        } else {
+            &[1.0]
+        };
+        let stride = ((decades as f64) / 8.0).ceil().max(1.0) as i32;
+        let mut ticks = Vec::new();
+        for e in (lo..=hi).step_by(stride as usize) {
+            for m in multipliers {
+                let v = m * 10f64.powi(e);
+                if v <= 10f64.powi(hi) {
+                    ticks.push(v);
+                }
+            }
+        }
+        Scale {
+            kind: ScaleType::Log,
+            domain: (10f64.powi(lo), 10f64.powi(hi)),
+            range,
+            ticks,
+            step: 0.0,
+        }
+    }
+
+    fn time(min: f64, max: f64, range: (f64, f64)) -> Self {
+        // The calendar arithmetic only covers years 0 through 9999
+        let (min, max) = (
+            min.clamp(MIN_TIMESTAMP, MAX_TIMESTAMP),
+            max.clamp(MIN_TIMESTAMP, MAX_TIMESTAMP),
+        );
+        let (min, max) = if min == max {
+            (
+                min - SECONDS_PER_DAY as f64 / 2.0,
+                max + SECONDS_PER_DAY as f64 / 2.0,
+            )
+        } else {
+            (min, max)
+        };
+        let span = max - min;
+        let interval = TIME_INTERVALS
+            .iter()
+            .copied()
+            .find(|&i| span / i <= 8.0)
+            .unwrap_or_else(|| {
+                let years = nice_step(span / YEAR / TARGET_TICKS).max(1.0);
+                years * YEAR
+            });
+
+        let mut ticks = Vec::new();
+        if interval < MONTH {
+            // Fixed-length intervals align to multiples since the epoch (UTC)
+            let mut t = (min / interval).ceil() * interval;
+            while t <= max {
+                ticks.push(t);
+                t += interval;
+            }
+        } else {
+            // Months and years follow the calendar
+            let months = (interval / MONTH).round() as i64;
+            let (y, m, _) = civil_from_days((min / SECONDS_PER_DAY as f64).floor() as i64);
+            let mut index = y * 12 + (m as i64 - 1);
+            index = (index + months - 1).div_euclid(months) * months;
+            loop {
+                let days =
+                    days_from_civil(index.div_euclid(12), (index.rem_euclid(12) + 1) as u32, 1);
+                let t = (days * SECONDS_PER_DAY) as f64;
+                if t > max {
+                    break;
+                }
+                if t >= min {
+                    ticks.push(t);
+                }
+                index += months;
+            }
+        }
+
+        Scale {
+            kind: ScaleType::Time,
+            domain: (min, max),
+            range,
+            ticks,
+            step: interval,
+        }
+    }
+
+    pub fn ticks(&self) -> &[f64] {
+        &self.ticks
+    }
+
+    // Data value to pixel coordinate
+    pub fn map(&self, value: f64) -> f64 {
+        let (d0, d1) = self.domain;
+        let t = match self.kind {
+            ScaleType::Log => {
+                let v = value.max(d0);
+                (v.log10() - d0.log10()) / (d1.log10() - d0.log10())
+            }
+            _ => split(d0, value, 2.0) / split(d0, d1, 2.0),
+        };
+        self.range.0 + t * (self.range.1 - self.range.0)
+    }
+
+    // Pixel coordinate bars and areas grow from: zero when it lies inside the domain,
+    // otherwise the lower end of the domain
+    pub fn baseline(&self) -> f64 {
+        self.map(self.baseline_value())
+    }
+
+    pub fn baseline_value(&self) -> f64 {
+        let (d0, d1) = self.domain;
+        if self.kind == ScaleType::Linear && d0 <= 0.0 && d1 >= 0.0 {
+            0.0
+        } else {
+            d0
+        }
+    }
+
+    // Whether the zero line falls strictly inside the domain
+    pub fn crosses_zero(&self) -> bool {
+        self.kind == ScaleType::Linear && self.domain.0 < 0.0 && self.domain.1 > 0.0
+    }
+
+    // Label for a data value: the number itself, or the date on time axes
+    pub fn format_value(&self, value: f64) -> String {
+        match self.kind {
+            ScaleType::Time => self.format_tick(value),
+            _ => super::format_number(value),
+        }
+    }
+
+    pub fn format_tick(&self, value: f64) -> String {
+        match self.kind {
+            ScaleType::Linear => format_fixed(value, decimals_for(self.step)),
+            ScaleType::Log => format_fixed(value, decimals_for(value)),
+            ScaleType::Time => format_time(value, self.step),
+        }
+    }
+}
+
+const MINUTE: f64 = 60.0;
+const HOUR: f64 = 3600.0;
+const DAY: f64 = SECONDS_PER_DAY as f64;
+const MONTH: f64 = 30.0 * DAY;
+const YEAR: f64 = 12.0 * MONTH;
+const TIME_INTERVALS: [f64; 21] = [
+    1.0,
+    5.0,
+    15.0,
+    30.0,
+    MINUTE,
+    5.0 * MINUTE,
+    15.0 * MINUTE,
+    30.0 * MINUTE,
+    HOUR,
+    3.0 * HOUR,
+    6.0 * HOUR,
+    12.0 * HOUR,
+    DAY,
+    2.0 * DAY,
+    7.0 * DAY,
+    14.0 * DAY,
+    MONTH,
+    3.0 * MONTH,
+    6.0 * MONTH,
+    YEAR,
+    2.0 * YEAR,
+];
+
+// (max - min) / parts for at least 2 parts. Divided before subtracting, as max - min
+// overflows to infinity for values near f64::MAX.
+pub fn split(min: f64, max: f64, parts: f64) -> f64 {
+    max / parts - min / parts
+}
+
+// Round a raw step up to 1, 2, 2.5 or 5 times a power of ten
+pub fn nice_step(raw: f64) -> f64 {
+    if !raw.is_finite() || raw <= 0.0 {
+        return 1.0;
+    }
+    let exp = raw.log10().floor();
+    let base = 10f64.powf(exp);
+    let frac = raw / base;
+    let nice_frac = if frac <= 1.0 {
+        1.0
+    } else if frac <= 2.0 {
+        2.0
+    } else if frac <= 2.5 {
+        2.5
+    } else if frac <= 5.0 {
+        5.0
+    } else {
+        10.0
+    };
+    // Rounding up can pass the largest finite value
+    (nice_frac * base).min(f64::MAX)
+}
+
+// Number of decimals needed to print multiples of step exactly
+fn decimals_for(step: f64) -> usize {
+    (0..=6)
+        .find(|&d| {
+            let scaled = step.abs() * 10f64.powi(d as i32);
+            (scaled - scaled.round()).abs() < 1e-6
+        })
+        .unwrap_or(6)
+}
+
+// A value on a grid of the given spacing, e.g. a histogram bin edge. Uneven spacings
+// are printed to about a hundredth of their size.
+pub fn format_on_grid(value: f64, spacing: f64) -> String {
+    let decimals = decimals_for(spacing).min(decimals_for(nice_step(spacing) / 100.0));
+    format_fixed(value, decimals)
+}
+
+fn format_fixed(value: f64, decimals: usize) -> String {
+    // Avoid printing "-0"
+    let value = if value.abs() < 1e-12 { 0.0 } else { value };
+    format!("{:.*}", decimals, value)
+}
+
+fn format_time(timestamp: f64, interval: f64) -> String {
+    let secs = timestamp.clamp(MIN_TIMESTAMP, MAX_TIMESTAMP).floor() as i64;
+    let days = secs.div_euclid(SECONDS_PER_DAY);
+    let rem = secs.rem_euclid(SECONDS_PER_DAY);
+    let (y, m, d) = civil_from_days(days);
+    let (hh, mm, ss) = (rem / 3600, rem % 3600 / 60, rem % 60);
+    if interval >= YEAR {
+        format!("{}", y)
+    } else if interval >= MONTH {
+        format!("{}-{:02}", y, m)
+    } else if interval >= DAY {
+        format!("{:02}-{:02}", m, d)
+    } else if interval >= MINUTE {
+        format!("{:02}:{:02}", hh, mm)
+    } else {
+        format!("{:02}:{:02}:{:02}", hh, mm, ss)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn assert_finite(scale: &Scale, values: &[f64]) {
+        assert!(!scale.ticks().is_empty());
+        for &v in scale.ticks().iter().chain(values) {
+            let y = scale.map(v);
+            assert!(y.is_finite() && (0.0..=100.0).contains(&y), "{v} -> {y}");
+        }
+    }
+
+    #[test]
+    fn linear_scale_survives_the_largest_floats() {
+        let scale = Scale::new(ScaleType::Linear, -1e308, 1e308, (0.0, 100.0));
+        assert_finite(&scale, &[-1e308, 0.0, 1e308]);
+        let scale = Scale::new(ScaleType::Linear, f64::MIN, f64::MAX, (0.0, 100.0));
+        assert_finite(&scale, &[f64::MIN, f64::MAX]);
+        assert!(scale.ticks().len() <= MAX_TICKS + 1);
+    }
+
+    #[test]
+    fn time_scale_stays_within_the_calendar() {
+        let scale = Scale::new(ScaleType::Time, 0.0, 1e300, (0.0, 100.0));
+        assert_finite(&scale, &[0.0]);
+        let scale = Scale::new(ScaleType::Time, -1e300, 0.0, (0.0, 100.0));
+        assert_finite(&scale, &[0.0]);
+        assert_eq!(scale.format_tick(scale.ticks()[0]), "0");
+    }
+
+    #[test]
+    fn log_scale_floor_stays_positive() {
+        let scale = Scale::new(ScaleType::Log, 5e-324, 10.0, (0.0, 100.0));
+        assert_finite(&scale, &[5e-324, 1.0, 10.0]);
+        let scale = Scale::new(ScaleType::Log, 1.0, f64::MAX, (0.0, 100.0));
+        assert_finite(&scale, &[1.0, 1e300]);
+    }
+}
@@ -1,82 +1,221 @@
-use crate::models::Series;
+use super::layout::Layout;
+use super::scale::Scale;
+use super::text;
+use super::theme::Theme;
+use resvg::usvg::{self, TreeWriting};
 
-pub fn create_svg_header(
+// A title longer than this wraps and ends with an ellipsis
+const MAX_TITLE_LINES: usize = 3;
+
+// Text inherits the theme's foreground color from the top-level group
+pub fn create_svg_root(layout: &Layout, theme: &Theme, transform: (f64, f64)) -> String {
+    format!(
+        r#"<?xml version="1.0" encoding="UTF-8" standalone="no"?>
+<svg width="{w}" height="{h}" xmlns="http://www.w3.org/2000/svg">
+<rect width="{w}" height="{h}" fill="{}"/>
+<g transform="translate({}, {})" fill="{}">"#,
+        theme.background,
+        transform.0,
+        transform.1,
+        theme.foreground,
+        w = layout.width,
+        h = layout.height,
+    )
+}
+
+// Width a title may take, leaving a small margin on both sides of the canvas
+fn title_width(layout: &Layout) -> f64 {
+    layout.width - 80.0 * layout.margin_ratio()
+}
+
+// The title broken into the lines it is drawn with
+pub fn title_lines(layout: &Layout, theme: &Theme, title: &str) -> Vec<String> {
+    text::wrap(
+        title,
+        title_width(layout),
+        theme.title_font_size,
+        MAX_TITLE_LINES,
+    )
+}
+
+// Title lines centered on `center` (the first baseline), in canvas coordinates. The
+// block moves towards the middle where it would run off the canvas.
+pub fn title(
+    layout: &Layout,
+    theme: &Theme,
+    title: &str,
+    center: (f64, f64),
+    translate: (f64, f64),
+) -> String {
+    let font_size = theme.title_font_size;
+    let line_height = text::line_height(font_size);
+    let margin = (layout.width - title_width(layout)) / 2.0;
+    let lines = title_lines(layout, theme, title);
+    let half = lines
+        .iter()
+        .map(|line| text::text_width(line, font_size) / 2.0)
+        .fold(0.0, f64::max);
+    let x = center
+        .0
+        .min(layout.width - margin - half)
+        .max(margin + half);
+    let mut svg = String::new();
+    for (i, line) in lines.iter().enumerate() {
+        svg.push_str(&format!(
+            r#"<text x="{}" y="{}" text-anchor="middle" font-family="M PLUS 1p" font-size="{}">{}</text>"#,
+            x - translate.0,
+            center.1 + i as f64 * line_height - translate.1,
+            font_size,
+            escape_text(line)
+        ));
+    }
+    svg
+}
+
+fn push_labels(
+    svg: &mut String,
+    layout: &Layout,
+    theme: &Theme,
     title: Option<&str>,
     x_label: Option<&str>,
     y_label: Option<&str>,
-) -> String {
-    let mut svg = format!(
-        r#"<?xml version="1.0" encoding="UTF-8" standalone="no"?>
-<svg width="800" height="600" xmlns="http://www.w3.org/2000/svg">
-<rect width="800" height="600" fill="white"/>
-<g transform="translate(80, 50)">"#
-    );
+) {
+    let k = layout.margin_ratio();
+    let origin = (layout.origin_x, layout.origin_y);
 
-    if let Some(title) = title {
-        svg.push_str(&format!(
-            r#"<text x="320" y="30" text-anchor="middle" font-family="M PLUS 1p" font-size="20">{}</text>"#,
-            title
+    if let Some(text) = title {
+        svg.push_str(&self::title(
+            layout,
+            theme,
+            text,
+            (
+                layout.origin_x + layout.center_x(),
+                layout.origin_y + 30.0 * k,
+            ),
+            origin,
         ));
     }
 
     if let Some(x_label) = x_label {
         svg.push_str(&format!(
-            r#"<text x="320" y="520" text-anchor="middle" font-family="M PLUS 1p" font-size="14">{}</text>"#,
-            x_label
+            r#"<text x="{}" y="{}" text-anchor="middle" font-family="M PLUS 1p" font-size="{}">{}</text>"#,
+            layout.center_x(),
+            layout.plot_bottom + 55.0 + 15.0 * k,
+            theme.label_font_size,
+            escape_text(&text::truncate_to_width(
+                x_label,
+                layout.plot_width,
+                theme.label_font_size
+            ))
         ));
     }
 
+    // Rotated, so it runs along the plot's height
     if let Some(y_label) = y_label {
         svg.push_str(&format!(
-            r#"<text x="-280" y="-50" text-anchor="middle" font-family="M PLUS 1p" font-size="14" transform="rotate(-90)">{}</text>"#,
-            y_label
+            r#"<text x="{}" y="{}" text-anchor="middle" font-family="M PLUS 1p" font-size="{}" transform="rotate(-90)">{}</text>"#,
+            -(layout.plot_top + layout.plot_bottom) / 2.0,
+            -layout.origin_x + 30.0 * k,
+            theme.label_font_size,
+            escape_text(&text::truncate_to_width(
+                y_label,
+                layout.plot_bottom - layout.plot_top,
+                theme.label_font_size
+            ))
         ));
     }
+}
 
-    // Draw axes
-    svg.push_str(
-        r#"<line x1="0" y1="450" x2="640" y2="450" stroke="black" stroke-width="2"/>
-<line x1="0" y1="50" x2="0" y2="450" stroke="black" stroke-width="2"/>"#,
-    );
+// Same layout with a left margin wide enough for the y tick labels, and for the y-axis
+// label left of them
+pub fn fit_y_ticks(layout: Layout, theme: &Theme, scale: &Scale, has_label: bool) -> Layout {
+    let widest = scale
+        .ticks()
+        .iter()
+        .map(|&value| text::text_width(&scale.format_tick(value), theme.tick_font_size))
+        .fold(0.0, f64::max);
+    fit_left_labels(layout, theme, widest, has_label)
+}
 
-    svg
+// Same layout with a left margin for labels up to `widest` wide that end 10px left of
+// the plot
+pub fn fit_left_labels(layout: Layout, theme: &Theme, widest: f64, has_label: bool) -> Layout {
+    let needed = left_label_inset(&layout, theme, has_label) + widest;
+    if needed <= layout.origin_x {
+        layout
+    } else {
+        layout.with_left_margin(needed.min(layout.width * 0.4))
+    }
 }
 
-pub fn create_svg_footer() -> &'static str {
-    "</g></svg>"
+// Width the left margin leaves for such labels
+pub fn left_label_room(layout: &Layout, theme: &Theme, has_label: bool) -> f64 {
+    (layout.origin_x - left_label_inset(layout, theme, has_label)).max(0.0)
 }
 
-// Variant without axes (for radar etc.)
-pub fn create_svg_header_no_axes(
+// Left margin taken by everything but the labels: the y-axis label and the gaps
+fn left_label_inset(layout: &Layout, theme: &Theme, has_label: bool) -> f64 {
+    let k = layout.margin_ratio();
+    let label = if has_label {
+        30.0 * k + text::descent(theme.label_font_size)
+    } else {
+        0.0
+    };
+    // Tick labels end 10px left of the axis
+    label + 4.0 * k + 10.0
+}
+
+pub fn create_svg_header(
+    layout: &Layout,
+    theme: &Theme,
     title: Option<&str>,
     x_label: Option<&str>,
     y_label: Option<&str>,
 ) -> String {
-    let mut svg = format!(
-        r#"<?xml version="1.0" encoding="UTF-8" standalone="no"?>
-<svg width="800" height="600" xmlns="http://www.w3.org/2000/svg">
-<rect width="800" height="600" fill="white"/>
-<g transform="translate(0, 0)">"#
-    );
+    let mut svg = create_svg_header_labels(layout, theme, title, x_label, y_label);
 
-    if let Some(title) = title {
-        svg.push_str(&format!(
-            r#"<text x="320" y="30" text-anchor="middle" font-family="M PLUS 1p" font-size="20">{}</text>"#,
-            title
-        ));
-    }
+    // Draw axes
+    svg.push_str(&format!(
+        r#"<line x1="0" y1="{b}" x2="{}" y2="{b}" stroke="{c}" stroke-width="{w}"/>
+<line x1="0" y1="{}" x2="0" y2="{b}" stroke="{c}" stroke-width="{w}"/>"#,
+        layout.plot_width,
+        layout.plot_top,
+        b = layout.plot_bottom,
+        c = theme.axis,
+        w = theme.axis_width,
+    ));
 
-    if let Some(x_label) = x_label {
-        svg.push_str(&format!(
-            r#"<text x="320" y="520" text-anchor="middle" font-family="M PLUS 1p" font-size="14">{}</text>"#,
-            x_label
-        ));
-    }
+    svg
+}
 
-    if let Some(y_label) = y_label {
-        svg.push_str(&format!(
-            r#"<text x="-280" y="-50" text-anchor="middle" font-family="M PLUS 1p" font-size="14" transform="rotate(-90)">{}</text>"#,
-            y_label
+// Title and axis labels without axis lines, for charts made of cells
+pub fn create_svg_header_labels(
+    layout: &Layout,
+    theme: &Theme,
+    title: Option<&str>,
+    x_label: Option<&str>,
+    y_label: Option<&str>,
+) -> String {
+    let mut svg = create_svg_root(layout, theme, (layout.origin_x, layout.origin_y));
+    push_labels(&mut svg, layout, theme, title, x_label, y_label);
+    svg
+}
+
+pub fn create_svg_footer() -> &'static str {
+    "</g></svg>"
+}
+
+// Variant without axes (for radar etc.); drawn in canvas coordinates
+pub fn create_svg_header_no_axes(layout: &Layout, theme: &Theme, title: Option<&str>) -> String {
+    let mut svg = create_svg_root(layout, theme, (0.0, 0.0));
+
+    if let Some(text) = title {
+        svg.push_str(&self::title(
+            layout,
+            theme,
+            text,
+            (layout.width / 2.0, 30.0 * layout.margin_ratio()),
+            (0.0, 0.0),
         ));
     }
 
@@ -84,112 +223,254 @@ pub fn create_svg_header_no_axes(
     svg
 }
 
-// Return a "nice" rounded max value for axis scaling (e.g., 37 -> 40, 0.87 -> 1.0)
-pub fn nice_max(max_value: f64) -> f64 {
-    if !max_value.is_finite() || max_value <= 0.0 {
-        return 1.0;
-    }
-    let exp = max_value.log10().floor();
-    let base = 10f64.powf(exp);
-    let frac = max_value / base;
-    let nice_frac = if frac <= 1.0 {
-        1.0
-    } else if frac <= 2.0 {
-        2.0
-    } else if frac <= 2.5 {
-        2.5
-    } else if frac <= 5.0 {
-        5.0
-    } else {
-        10.0
-    };
-    nice_frac * base
+// Shown instead of a chart when there is nothing to plot
+pub fn create_placeholder(
+    layout: &Layout,
+    theme: &Theme,
+    title: Option<&str>,
+    message: &str,
+) -> String {
+    let mut svg = create_svg_header_no_axes(layout, theme, title);
+    svg.push_str(&format!(
+        r#"<text x="{}" y="{}" text-anchor="middle" font-family="M PLUS 1p" font-size="{}" fill="{}">{}</text>"#,
+        layout.width / 2.0,
+        layout.height / 2.0,
+        theme.label_font_size,
+        theme.muted,
+        escape_text(message)
+    ));
+    svg.push_str(create_svg_footer());
+    svg
 }
 
-pub fn create_legend(series: &[Series], x: f64, y: f64) -> String {
-    let mut legend = String::new();
-    let mut y_offset = y;
-
-    for series in series {
-        if let Some(name) = &series.name {
-            let default_color = "#000000".to_string();
-            let color = series.color.as_ref().unwrap_or(&default_color);
-            legend.push_str(&format!(
-                r#"<rect x="{}" y="{}" width="20" height="20" fill="{}" />"#,
-                x, y_offset, color
-            ));
-            legend.push_str(&format!(
-                r#"<text x="{}" y="{}" font-family="M PLUS 1p" font-size="12">{}</text>"#,
-                x + 25.0,
-                y_offset + 15.0,
-                name
-            ));
-            y_offset += 25.0;
-        }
+// Solid line at zero when the domain spans negative and positive values
+pub fn generate_zero_line(layout: &Layout, theme: &Theme, scale: &Scale) -> String {
+    if !scale.crosses_zero() {
+        return String::new();
     }
-
-    legend
+    format!(
+        r#"<line x1="0" y1="{y}" x2="{}" y2="{y}" stroke="{}" stroke-width="{}"/>"#,
+        layout.plot_width,
+        theme.axis,
+        theme.axis_width / 2.0,
+        y = scale.baseline()
+    )
 }
 
-pub fn generate_y_axis_ticks(max_value: f64) -> String {
-    let num_ticks = 5;
-    let tick_step = max_value / num_ticks as f64;
+pub fn generate_y_axis_ticks(layout: &Layout, theme: &Theme, scale: &Scale) -> String {
     let mut ticks = String::new();
 
-    for i in 0..=num_ticks {
-        let y = 450.0 - ((450.0 - 50.0) * i as f64 / num_ticks as f64);
-        let value = tick_step * i as f64;
+    for &value in scale.ticks() {
+        let y = scale.map(value);
         // Draw tick mark and label
         ticks.push_str(&format!(
-            r#"<line x1="-5" y1="{y}" x2="0" y2="{y}" stroke="black" stroke-width="2"/>
-            <text x="-10" y="{}" text-anchor="end" font-family="M PLUS 1p" font-size="12">{:.1}</text>"#,
-            y + 4.0,
-            value
+            r#"<line x1="-5" y1="{y}" x2="0" y2="{y}" stroke="{}" stroke-width="{}"/>
+            <text x="-10" y="{}" text-anchor="end" font-family="M PLUS 1p" font-size="{}">{}</text>"#,
+            theme.axis,
+            theme.axis_width,
+            y + theme.tick_font_size / 3.0,
+            theme.tick_font_size,
+            scale.format_tick(value)
         ));
         // Draw grid line
         ticks.push_str(&format!(
-            r#"<line x1="0" y1="{0}" x2="640" y2="{0}" stroke="{1}" stroke-width="{2}" stroke-dasharray="{3}" />"#,
-            y, "#CCCCCC", 1, 4
+            r#"<line x1="0" y1="{0}" x2="{4}" y2="{0}" stroke="{1}" stroke-width="{2}" stroke-dasharray="{3}" />"#,
+            y, theme.grid, 1, 4, layout.plot_width
         ));
     }
     ticks
 }
 
-pub fn generate_x_axis_ticks_for_line(num_points: usize) -> String {
+// Horizontal distance between consecutive points on a line-style x-axis
+pub fn line_segment_width(layout: &Layout, num_points: usize) -> f64 {
+    if num_points > 1 {
+        layout.plot_width / (num_points as f64 - 1.0)
+    } else {
+        layout.plot_width
+    }
+}
+
+// x position of a point on a line-style axis; a lone point sits in the middle
+pub fn line_point_x(layout: &Layout, index: usize, num_points: usize) -> f64 {
+    if num_points > 1 {
+        index as f64 * line_segment_width(layout, num_points)
+    } else {
+        layout.plot_width / 2.0
+    }
+}
+
+pub fn generate_x_axis_ticks_for_line(layout: &Layout, theme: &Theme, labels: &[String]) -> String {
+    let segment_width = line_segment_width(layout, labels.len());
+    let positions: Vec<f64> = (0..labels.len())
+        .map(|i| line_point_x(layout, i, labels.len()))
+        .collect();
+    generate_category_ticks(layout, theme, &positions, labels, segment_width)
+}
+
+pub fn generate_x_axis_ticks_for_bar(layout: &Layout, theme: &Theme, labels: &[String]) -> String {
+    let segment_width = layout.plot_width / labels.len() as f64;
+    let positions: Vec<f64> = (0..labels.len())
+        .map(|i| i as f64 * segment_width + (segment_width / 2.0))
+        .collect();
+    generate_category_ticks(layout, theme, &positions, labels, segment_width)
+}
+
+// Draw category ticks at the given x positions. Labels that would overlap their
+// neighbours are rotated by 45 degrees, thinned out to every n-th label and shortened
+// with an ellipsis so they stay inside the bottom margin.
+fn generate_category_ticks(
+    layout: &Layout,
+    theme: &Theme,
+    positions: &[f64],
+    labels: &[String],
+    spacing: f64,
+) -> String {
+    let font_size = theme.tick_font_size;
     let mut ticks = String::new();
-    let segment_width = 640.0 / (num_points as f64 - 1.0);
+    let widest = labels
+        .iter()
+        .map(|l| text::text_width(l, font_size))
+        .fold(0.0, f64::max);
+    let rotate = widest > spacing * 0.9;
+    let step = if rotate {
+        // Rotated labels need about one and a half line heights between them
+        (font_size * 1.5 / spacing).ceil().max(1.0) as usize
+    } else {
+        1
+    };
+    let bottom_margin = layout.bottom_margin();
+    let max_rotated_width = ((bottom_margin - 55.0) / std::f64::consts::FRAC_1_SQRT_2).max(30.0);
 
-    for i in 0..num_points {
-        let x = i as f64 * segment_width;
+    for (i, (&x, label)) in positions.iter().zip(labels).enumerate() {
         ticks.push_str(&format!(
-            r#"<line x1="{x}" y1="450" x2="{x}" y2="460" stroke="black" stroke-width="2"/>
-            <text x="{x}" y="480" text-anchor="middle" font-family="M PLUS 1p" font-size="12">{}</text>"#,
-            i + 1
+            r#"<line x1="{x}" y1="{}" x2="{x}" y2="{}" stroke="{}" stroke-width="{}"/>"#,
+            layout.plot_bottom,
+            layout.plot_bottom + 10.0,
+            theme.axis,
+            theme.axis_width,
         ));
+        if i % step != 0 {
+            continue;
+        }
+        if rotate {
+            let y = layout.plot_bottom + 20.0;
+            ticks.push_str(&format!(
+                r#"<text x="{x}" y="{y}" text-anchor="end" font-family="M PLUS 1p" font-size="{font_size}" transform="rotate(-45, {x}, {y})">{}</text>"#,
+                escape_text(&text::truncate_to_width(label, max_rotated_width, font_size))
+            ));
+        } else {
+            ticks.push_str(&format!(
+                r#"<text x="{x}" y="{}" text-anchor="middle" font-family="M PLUS 1p" font-size="{font_size}">{}</text>"#,
+                layout.plot_bottom + 30.0,
+                escape_text(label)
+            ));
+        }
     }
     ticks
 }
 
-pub fn generate_x_axis_ticks_for_bar(num_points: usize) -> String {
+// Numeric x-axis ticks for charts that place values horizontally (scatter,
+// horizontal bars)
+pub fn generate_x_axis_ticks_for_scale(layout: &Layout, theme: &Theme, scale: &Scale) -> String {
     let mut ticks = String::new();
-    let segment_width = 640.0 / num_points as f64;
 
-    for i in 0..num_points {
-        let x = i as f64 * segment_width + (segment_width / 2.0);
+    for &value in scale.ticks() {
+        let x = scale.map(value);
+        ticks.push_str(&format!(
+            r#"<line x1="{x}" y1="{b}" x2="{x}" y2="{}" stroke="{}" stroke-width="{}"/>
+            <text x="{x}" y="{}" text-anchor="middle" font-family="M PLUS 1p" font-size="{}">{}</text>"#,
+            layout.plot_bottom + 10.0,
+            theme.axis,
+            theme.axis_width,
+            layout.plot_bottom + 30.0,
+            theme.tick_font_size,
+            scale.format_tick(value),
+            b = layout.plot_bottom,
+        ));
+        // Draw grid line (the y axis already covers the left edge)
+        if x > 0.0 {
+            ticks.push_str(&format!(
+                r#"<line x1="{0}" y1="{1}" x2="{0}" y2="{2}" stroke="{3}" stroke-width="{4}" stroke-dasharray="{5}" />"#,
+                x, layout.plot_top, layout.plot_bottom, theme.grid, 1, 4
+            ));
+        }
+    }
+    ticks
+}
+
+// Ticks at the given x positions with numeric labels, e.g. the edges of histogram
+// bins. Labels are thinned out to every n-th one where neighbours would overlap.
+pub fn generate_x_axis_ticks_at(
+    layout: &Layout,
+    theme: &Theme,
+    positions: &[f64],
+    labels: &[String],
+) -> String {
+    let font_size = theme.tick_font_size;
+    let spacing = match positions {
+        [first, .., last] => (last - first) / (positions.len() - 1) as f64,
+        _ => layout.plot_width,
+    };
+    let widest = labels
+        .iter()
+        .map(|l| text::text_width(l, font_size))
+        .fold(0.0, f64::max);
+    let step = ((widest + 8.0) / spacing).ceil().max(1.0) as usize;
+
+    let mut ticks = String::new();
+    for (i, (&x, label)) in positions.iter().zip(labels).enumerate() {
         ticks.push_str(&format!(
-            r#"<line x1="{x}" y1="450" x2="{x}" y2="460" stroke="black" stroke-width="2"/>
-            <text x="{x}" y="480" text-anchor="middle" font-family="M PLUS 1p" font-size="12">{}</text>"#,
-            i + 1
+            r#"<line x1="{x}" y1="{}" x2="{x}" y2="{}" stroke="{}" stroke-width="{}"/>"#,
+            layout.plot_bottom,
+            layout.plot_bottom + if i % step == 0 { 10.0 } else { 5.0 },
+            theme.axis,
+            theme.axis_width,
         ));
+        if i % step == 0 {
+            ticks.push_str(&format!(
+                r#"<text x="{x}" y="{}" text-anchor="middle" font-family="M PLUS 1p" font-size="{font_size}">{}</text>"#,
+                layout.plot_bottom + 30.0,
+                escape_text(label)
+            ));
+        }
     }
     ticks
 }
 
-pub fn generate_value_text(x: f64, y: f64, value: f64) -> String {
+pub fn generate_value_text(theme: &Theme, x: f64, y: f64, value: f64, text: &str) -> String {
+    // Negative values hang below their point instead of sitting above it
+    let text_y = if value < 0.0 { y + 15.0 } else { y - 5.0 };
     format!(
-        r#"<text x="{}" y="{}" text-anchor="middle" font-family="M PLUS 1p" font-size="12">{}</text>"#,
+        r#"<text x="{}" y="{}" text-anchor="middle" font-family="M PLUS 1p" font-size="{}">{}</text>"#,
         x,
-        y - 5.0,
-        super::format_number(value)
+        text_y,
+        theme.tick_font_size,
+        escape_text(text)
     )
 }
+
+// Re-serialize the chart with all text converted to paths, so the SVG renders with the
+// embedded M PLUS 1p glyphs even where the font is not installed.
+pub fn outline_text(svg_str: &str) -> Result<String, String> {
+    let tree = super::font::parse_svg_tree(svg_str)?;
+    Ok(tree.to_string(&usvg::XmlOptions::default()))
+}
+
+// Every piece of user-supplied text goes through here before it is written into
+// the SVG. Characters that XML 1.0 cannot represent at all are dropped.
+pub fn escape_text(text: &str) -> String {
+    let mut escaped = String::with_capacity(text.len());
+    for c in text.chars() {
+        match c {
+            '&' => escaped.push_str("&amp;"),
+            '<' => escaped.push_str("&lt;"),
+            '>' => escaped.push_str("&gt;"),
+            '"' => escaped.push_str("&quot;"),
+            '\'' => escaped.push_str("&apos;"),
+            '\t' | '\n' | '\r' => escaped.push(c),
+            c if c.is_control() || matches!(c, '\u{FFFE}' | '\u{FFFF}') => {}
+            c => escaped.push(c),
+        }
+    }
+    escaped
+}
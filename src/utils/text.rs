@@ -0,0 +1,201 @@
+use super::font;
+
+// Text metrics from the embedded M PLUS 1p face, so layout decisions match what the
+// renderer draws. Characters the font lacks are measured as its fallback glyph.
+
+// Full-width (CJK, kana, full-width forms) characters; a line may break between any two
+fn is_wide(c: char) -> bool {
+    matches!(c as u32,
+        0x1100..=0x115F
+        | 0x2E80..=0xA4CF
+        | 0xAC00..=0xD7A3
+        | 0xF900..=0xFAFF
+        | 0xFE30..=0xFE4F
+        | 0xFF00..=0xFF60
+        | 0xFFE0..=0xFFE6)
+}
+
+fn em(units: f64) -> f64 {
+    units / font::face().units_per_em() as f64
+}
+
+// Horizontal advance of a character in em
+fn advance(c: char) -> f64 {
+    let face = font::face();
+    let glyph = face.glyph_index(c).unwrap_or(ttf_parser::GlyphId(0));
+    em(face.glyph_hor_advance(glyph).unwrap_or(0) as f64)
+}
+
+pub fn text_width(text: &str, font_size: f64) -> f64 {
+    text.chars().map(advance).sum::<f64>() * font_size
+}
+
+// Height above the baseline
+pub fn ascent(font_size: f64) -> f64 {
+    em(font::face().ascender() as f64) * font_size
+}
+
+// Depth below the baseline, as a positive number
+pub fn descent(font_size: f64) -> f64 {
+    -em(font::face().descender() as f64) * font_size
+}
+
+// Distance between the baselines of consecutive lines
+pub fn line_height(font_size: f64) -> f64 {
+    ascent(font_size) + descent(font_size) + em(font::face().line_gap() as f64) * font_size
+}
+
+// Shorten text with a trailing ellipsis so that it fits into max_width
+pub fn truncate_to_width(text: &str, max_width: f64, font_size: f64) -> String {
+    if text_width(text, font_size) <= max_width {
+        return text.to_string();
+    }
+    let ellipsis = "…";
+    let budget = max_width - text_width(ellipsis, font_size);
+    let mut result = String::new();
+    let mut width = 0.0;
+    for c in text.chars() {
+        let w = advance(c) * font_size;
+        if width + w > budget {
+            break;
+        }
+        width += w;
+        result.push(c);
+    }
+    result.push_str(ellipsis);
+    result
+}
+
+// Break text into lines no wider than max_width: at spaces, between full-width
+// characters and at explicit newlines, and inside words that are too long on their
+// own. Text beyond max_lines is cut off with an ellipsis on the last line.
+pub fn wrap(text: &str, max_width: f64, font_size: f64, max_lines: usize) -> Vec<String> {
+    // Byte offset where each line starts
+    let mut starts = Vec::new();
+    let mut lines: Vec<String> = Vec::new();
+    for (offset, paragraph) in paragraphs(text) {
+        let mut line = String::new();
+        let mut line_start = offset;
+        for (i, segment) in segments(paragraph) {
+            let candidate = format!("{line}{segment}");
+            if line.is_empty() || text_width(candidate.trim_end(), font_size) <= max_width {
+                line = candidate;
+                continue;
+            }
+            starts.push(line_start);
+            lines.push(line.trim_end().to_string());
+            line = segment.to_string();
+            line_start = offset + i;
+        }
+        starts.push(line_start);
+        lines.push(line.trim_end().to_string());
+    }
+
+    // Words that are too long for a line of their own are broken anywhere
+    let mut broken = Vec::new();
+    let mut broken_starts = Vec::new();
+    for (line, start) in lines.into_iter().zip(starts) {
+        let mut rest = line.as_str();
+        let mut offset = start;
+        while text_width(rest, font_size) > max_width && rest.chars().count() > 1 {
+            let mut width = 0.0;
+            let split = rest
+                .char_indices()
+                .find(|&(i, c)| {
+                    width += advance(c) * font_size;
+                    i > 0 && width > max_width
+                })
+                .map_or(rest.len(), |(i, _)| i);
+            broken.push(rest[..split].to_string());
+            broken_starts.push(offset);
+            offset += split;
+            rest = &rest[split..];
+        }
+        broken.push(rest.to_string());
+        broken_starts.push(offset);
+    }
+
+    if broken.len() > max_lines.max(1) {
+        let last = max_lines.max(1) - 1;
+        let remainder = text[broken_starts[last]..].replace('\n', " ");
+        broken.truncate(last);
+        broken.push(truncate_to_width(remainder.trim(), max_width, font_size));
+    }
+    broken
+}
+
+// Lines separated by explicit newlines, with their byte offsets
+fn paragraphs(text: &str) -> impl Iterator<Item = (usize, &str)> {
+    text.split('\n').scan(0, |offset, paragraph| {
+        let start = *offset;
+        *offset += paragraph.len() + 1;
+        Some((start, paragraph))
+    })
+}
+
+// Pieces that stay on one line: a word with its trailing spaces, or a single
+// full-width character
+fn segments(text: &str) -> Vec<(usize, &str)> {
+    let mut segments = Vec::new();
+    let mut start = 0;
+    let mut chars = text.char_indices().peekable();
+    while let Some((i, c)) = chars.next() {
+        let end = i + c.len_utf8();
+        let next = chars.peek().map(|&(_, n)| n);
+        let breaks_after = match next {
+            None => true,
+            Some(n) => {
+                (c.is_whitespace() && !n.is_whitespace())
+                    || ((is_wide(c) || is_wide(n)) && !n.is_whitespace())
+            }
+        };
+        if breaks_after {
+            segments.push((start, &text[start..end]));
+            start = end;
+        }
+    }
+    segments
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn measures_with_the_font() {
+        // Kanji and kana are one em wide, Latin text is narrower and proportional
+        assert!((text_width("売上", 10.0) - 20.0).abs() < 0.01);
+        assert!(text_width("iiii", 10.0) < text_width("MMMM", 10.0));
+        assert!(text_width("Sales", 10.0) < 5.0 * 10.0);
+        assert!(line_height(10.0) > ascent(10.0) + descent(10.0) - 0.01);
+    }
+
+    #[test]
+    fn wraps_at_spaces_and_between_wide_characters() {
+        let width = text_width("users by region", 20.0);
+        assert_eq!(
+            wrap("Monthly active users by region", width, 20.0, 3),
+            ["Monthly active", "users by region"]
+        );
+        assert_eq!(
+            wrap("月間アクティブ", text_width("月間アク", 20.0), 20.0, 3),
+            ["月間アク", "ティブ"]
+        );
+        assert_eq!(wrap("first\nsecond", 1000.0, 20.0, 3), ["first", "second"]);
+        // Words longer than a line are broken inside
+        assert_eq!(
+            wrap("abcdefgh", text_width("abcd", 20.0), 20.0, 3),
+            ["abcd", "efgh"]
+        );
+    }
+
+    #[test]
+    fn cuts_off_lines_beyond_the_limit() {
+        let width = text_width("one two", 20.0);
+        let lines = wrap("one two three four five six", width, 20.0, 2);
+        assert_eq!(lines.len(), 2);
+        assert_eq!(lines[0], "one two");
+        assert!(lines[1].starts_with("three") && lines[1].ends_with('…'));
+        assert!(lines.iter().all(|l| text_width(l, 20.0) <= width));
+    }
+}
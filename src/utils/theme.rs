@@ -0,0 +1,187 @@
+use super::color;
+use super::palette::{self, Palette};
+use crate::models::{CustomTheme, GraphRequest, GraphType, ThemePreset, ThemeSpec};
+
+// Font sizes and stroke widths outside this range would break the layout
+const MIN_FONT_SIZE: f64 = 6.0;
+const MAX_FONT_SIZE: f64 = 48.0;
+const MAX_STROKE_WIDTH: f64 = 10.0;
+
+// Colors, font sizes and stroke widths shared by every chart. Resolved once per
+// request from a preset, optionally with inline overrides.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Theme {
+    pub background: String,
+    pub foreground: String,
+    pub muted: String,
+    pub grid: String,
+    pub axis: String,
+    pub palette: Vec<String>,
+    pub title_font_size: f64,
+    pub label_font_size: f64,
+    pub tick_font_size: f64,
+    pub axis_width: f64,
+    pub line_width: f64,
+    // Candles that close above (up) or below (down) their open
+    pub up: String,
+    pub down: String,
+}
+
+fn palette(colors: &[&str]) -> Vec<String> {
+    colors.iter().map(|c| c.to_string()).collect()
+}
+
+// How many colors a ramp palette is split into: one per slice for pie charts, one per
+// series otherwise
+fn palette_size(request: &GraphRequest) -> usize {
+    let n = match request.graph_type {
+        GraphType::Pie | GraphType::Donut => request
+            .series
+            .first()
+            .map_or(request.data.len(), |s| s.data.len()),
+        _ => request.series.len(),
+    };
+    n.max(1)
+}
+
+impl Theme {
+    pub fn from_request(request: &GraphRequest) -> Self {
+        let mut theme = match &request.theme {
+            ThemeSpec::Preset(preset) => Theme::preset(*preset),
+            ThemeSpec::Custom(custom) => Theme::custom(custom),
+        };
+        if let Some(name) = request.palette {
+            theme.palette = Palette::named(name).colors(palette_size(request));
+        }
+        theme
+    }
+
+    pub fn preset(preset: ThemePreset) -> Self {
+        match preset {
+            ThemePreset::Light => Theme {
+                background: "white".to_string(),
+                foreground: "black".to_string(),
+                muted: "#999999".to_string(),
+                grid: "#CCCCCC".to_string(),
+                axis: "black".to_string(),
+                palette: palette(&[
+                    "#0000FF", "#FFB3B3", "#B3E0FF", "#FFE6B3", "#B3FFB3", "#E6B3FF", "#FFD9B3",
+                ]),
+                title_font_size: 20.0,
+                label_font_size: 14.0,
+                tick_font_size: 12.0,
+                axis_width: 2.0,
+                line_width: 2.0,
+                up: "#2E7D32".to_string(),
+                down: "#C62828".to_string(),
+            },
+            // Brighter series colors that stay readable on a dark background
+            ThemePreset::Dark => Theme {
+                background: "#1E1E1E".to_string(),
+                foreground: "#E0E0E0".to_string(),
+                muted: "#8A8A8A".to_string(),
+                grid: "#3C3C3C".to_string(),
+                axis: "#BDBDBD".to_string(),
+                palette: palette(&[
+                    "#4E9BFF", "#FF8A80", "#80D8FF", "#FFD180", "#B9F6CA", "#EA80FC", "#FFAB91",
+                ]),
+                up: "#66BB6A".to_string(),
+                down: "#EF5350".to_string(),
+                ..Theme::preset(ThemePreset::Light)
+            },
+            // Thin strokes, faint grid and muted colors, e.g. for print reports
+            ThemePreset::Minimal => Theme {
+                foreground: "#333333".to_string(),
+                muted: "#888888".to_string(),
+                grid: "#EEEEEE".to_string(),
+                axis: "#AAAAAA".to_string(),
+                palette: palette(palette::TABLEAU10),
+                title_font_size: 18.0,
+                label_font_size: 13.0,
+                tick_font_size: 11.0,
+                axis_width: 1.0,
+                line_width: 1.5,
+                up: "#59A14F".to_string(),
+                down: "#E15759".to_string(),
+                ..Theme::preset(ThemePreset::Light)
+            },
+            // Larger text, heavy strokes and a color-blind safe palette
+            ThemePreset::HighContrast => Theme {
+                muted: "#555555".to_string(),
+                grid: "#767676".to_string(),
+                palette: palette(&[
+                    "#0072B2", "#D55E00", "#009E73", "#CC79A7", "#000000", "#E69F00", "#56B4E9",
+                ]),
+                title_font_size: 24.0,
+                label_font_size: 16.0,
+                tick_font_size: 14.0,
+                axis_width: 3.0,
+                line_width: 3.0,
+                // Blue and orange tell up from down without relying on red and green
+                up: "#0072B2".to_string(),
+                down: "#D55E00".to_string(),
+                ..Theme::preset(ThemePreset::Light)
+            },
+        }
+    }
+
+    // Invalid colors and sizes keep the base preset's value
+    fn custom(custom: &CustomTheme) -> Self {
+        let base = Theme::preset(custom.base);
+        let color = |value: &Option<String>, fallback: String| {
+            color::sanitize_opt(value.as_ref()).map_or(fallback, str::to_string)
+        };
+        let size = |value: Option<f64>, fallback: f64, min: f64, max: f64| {
+            value
+                .filter(|v| v.is_finite() && *v > 0.0)
+                .map_or(fallback, |v| v.clamp(min, max))
+        };
+        let palette: Vec<String> = custom
+            .palette
+            .iter()
+            .flatten()
+            .filter_map(|c| color::sanitize(c))
+            .map(str::to_string)
+            .collect();
+
+        Theme {
+            background: color(&custom.background, base.background),
+            foreground: color(&custom.foreground, base.foreground),
+            muted: color(&custom.muted, base.muted),
+            grid: color(&custom.grid, base.grid),
+            axis: color(&custom.axis, base.axis),
+            palette: if palette.is_empty() {
+                base.palette
+            } else {
+                palette
+            },
+            title_font_size: size(
+                custom.title_font_size,
+                base.title_font_size,
+                MIN_FONT_SIZE,
+                MAX_FONT_SIZE,
+            ),
+            label_font_size: size(
+                custom.label_font_size,
+                base.label_font_size,
+                MIN_FONT_SIZE,
+                MAX_FONT_SIZE,
+            ),
+            tick_font_size: size(
+                custom.tick_font_size,
+                base.tick_font_size,
+                MIN_FONT_SIZE,
+                MAX_FONT_SIZE,
+            ),
+            axis_width: size(custom.axis_width, base.axis_width, 0.5, MAX_STROKE_WIDTH),
+            line_width: size(custom.line_width, base.line_width, 0.5, MAX_STROKE_WIDTH),
+            up: color(&custom.up, base.up),
+            down: color(&custom.down, base.down),
+        }
+    }
+
+    // Palette color for the n-th series (or slice), wrapping around
+    pub fn color(&self, index: usize) -> &str {
+        &self.palette[index % self.palette.len()]
+    }
+}
@@ -0,0 +1,153 @@
+// The builder and the JSON API must describe exactly the same requests.
+
+use rust_chart_worker::models::{
+    BinRule, Bins, DataPoint, Matrix, Orientation, Series, Stacking, Summary,
+};
+use rust_chart_worker::request::parse_json;
+use rust_chart_worker::ChartBuilder;
+
+#[test]
+fn fixtures_round_trip_through_serialize() {
+    let dir = std::path::Path::new(env!("CARGO_MANIFEST_DIR")).join("tests/fixtures");
+    for entry in std::fs::read_dir(dir).unwrap() {
+        let path = entry.unwrap().path();
+        let request = parse_json(&std::fs::read(&path).unwrap()).unwrap();
+        let json = serde_json::to_string(&request).unwrap();
+        let reparsed = parse_json(json.as_bytes()).unwrap();
+        assert_eq!(request, reparsed, "{}", path.display());
+    }
+}
+
+#[test]
+fn builder_renders_svg_and_png() {
+    let chart = ChartBuilder::scatter().series(Series::new("GET").data([(1.0, 30.0), (3.5, 45.0)]));
+    assert!(chart.render_svg().unwrap().starts_with("<svg"));
+    assert!(chart.render_png().unwrap().starts_with(b"\x89PNG"));
+}
+
+// Each builder next to the JSON body it has to produce
+fn builder_cases() -> Vec<(ChartBuilder, &'static [u8])> {
+    vec![
+        (
+            ChartBuilder::bar()
+                .title("Cost breakdown")
+                .y_label("USD")
+                .stacking(Stacking::Normal)
+                .orientation(Orientation::Horizontal)
+                .size(640.0, 480.0)
+                .series(
+                    Series::new("Compute")
+                        .color("#4E79A7")
+                        .data([("Jan", 100.0), ("Feb", 120.0)]),
+                )
+                .series(
+                    Series::new("Storage").data([DataPoint::new(40.0).color("#F28E2B"), 50.0.into()]),
+                ),
+            br##"{
+                "graph_type": "bar",
+                "title": "Cost breakdown",
+                "y_label": "USD",
+                "stacking": "normal",
+                "orientation": "horizontal",
+                "width": 640,
+                "height": 480,
+                "series": [
+                    {"name": "Compute", "color": "#4E79A7", "data": [{"value": 100, "label": "Jan"}, {"value": 120, "label": "Feb"}]},
+                    {"name": "Storage", "data": [{"value": 40, "color": "#F28E2B"}, 50]}
+                ]
+            }"##,
+        ),
+        (
+            ChartBuilder::heatmap().title("Incidents").matrix(
+                Matrix::new([vec![Some(1.0), None], vec![Some(3.0), Some(4.0)]])
+                    .rows(["Mon", "Tue"])
+                    .columns(["0h", "1h"])
+                    .show_values(true),
+            ),
+            br#"{
+                "graph_type": "heatmap",
+                "title": "Incidents",
+                "matrix": {"rows": ["Mon", "Tue"], "columns": ["0h", "1h"], "values": [[1, null], [3, 4]], "show_values": true}
+            }"#,
+        ),
+        (
+            ChartBuilder::histogram()
+                .bins(Bins::width(2.5))
+                .density(true)
+                .data([1.0, 2.0, 2.0, 7.0]),
+            br#"{"graph_type": "histogram", "bins": {"width": 2.5}, "density": true, "data": [1, 2, 2, 7]}"#,
+        ),
+        (
+            ChartBuilder::histogram().bins(12).data([1.0, 2.0]),
+            br#"{"graph_type": "histogram", "bins": 12, "data": [1, 2]}"#,
+        ),
+        (
+            ChartBuilder::histogram()
+                .bins(BinRule::FreedmanDiaconis)
+                .data([1.0, 2.0]),
+            br#"{"graph_type": "histogram", "bins": "freedman_diaconis", "data": [1, 2]}"#,
+        ),
+        (
+            ChartBuilder::boxplot()
+                .series(Series::new("api").data([120.0, 135.0, 150.0, 410.0]))
+                .series(
+                    Series::new("web")
+                        .summary(Summary::new(80.0, 95.0, 110.0, 130.0, 170.0).mean(112.0)),
+                )
+                .show_mean(true),
+            br#"{
+                "graph_type": "box",
+                "show_mean": true,
+                "series": [
+                    {"name": "api", "data": [120, 135, 150, 410]},
+                    {"name": "web", "summary": {"min": 80, "q1": 95, "median": 110, "q3": 130, "max": 170, "mean": 112}}
+                ]
+            }"#,
+        ),
+        (
+            ChartBuilder::candlestick().series(Series::default().data([
+                DataPoint::ohlc(10.0, 12.0, 9.5, 11.0)
+                    .label("03-01")
+                    .volume(1200.0),
+                DataPoint::ohlc(11.0, 11.5, 10.0, 10.2).label("03-04"),
+            ])),
+            br#"{
+                "graph_type": "candlestick",
+                "series": [{"data": [
+                    {"label": "03-01", "open": 10, "high": 12, "low": 9.5, "close": 11, "volume": 1200},
+                    {"label": "03-04", "open": 11, "high": 11.5, "low": 10, "close": 10.2}
+                ]}]
+            }"#,
+        ),
+    ]
+}
+
+#[test]
+fn builder_matches_json_request() {
+    for (built, body) in builder_cases() {
+        let json = parse_json(body).unwrap();
+        let context = String::from_utf8_lossy(body);
+        assert_eq!(built.request(), &json, "{context}");
+        assert_eq!(
+            parse_json(built.to_json().unwrap().as_bytes()).unwrap(),
+            json,
+            "{context}"
+        );
+        assert!(built.render_png().is_ok(), "{context}");
+    }
+}
+
+#[test]
+fn ohlc_prices_come_as_a_set() {
+    let request = parse_json(
+        br#"{"graph_type": "ohlc", "series": [{"data": [{"open": 11, "high": 11.5, "low": 10, "close": 10.2}]}]}"#,
+    )
+    .unwrap();
+    // The value defaults to the close
+    assert_eq!(request.series[0].data[0].value, 10.2);
+
+    let error =
+        parse_json(br#"{"graph_type": "ohlc", "series": [{"data": [{"open": 1, "close": 2}]}]}"#)
+            .unwrap_err();
+    assert_eq!(error.field(), Some("series[0].data[0]"));
+}
@@ -0,0 +1,229 @@
+// Golden snapshot tests.
+//
+// Every tests/fixtures/<name>.json is a GraphRequest that gets rendered to SVG (the
+// chart markup before text is converted to paths) and to PNG, and compared with
+// tests/golden/<name>.svg and tests/golden/<name>.png. SVGs must match exactly; PNGs
+// may differ by a few pixels so that small rasterizer differences between platforms
+// do not fail the build.
+//
+// On failure the actual output and a diff image (differing pixels in red) are written
+// to target/golden-diff/. After an intended rendering change, regenerate the goldens
+// with
+//
+//     UPDATE_GOLDEN=1 cargo test --test golden
+//
+// and review the changed images before committing them.
+
+use rust_chart_worker::models::{GraphRequest, OutputFormat};
+use std::path::{Path, PathBuf};
+
+// A pixel counts as changed when any channel differs by more than this
+const CHANNEL_TOLERANCE: u8 = 16;
+// Share of changed pixels allowed before a PNG snapshot fails
+const PIXEL_TOLERANCE: f64 = 0.001;
+
+// Every chart type must be covered by at least one fixture
+const GRAPH_TYPES: &[&str] = &[
+    "line",
+    "bar",
+    "scatter",
+    "pie",
+    "donut",
+    "area",
+    "radar",
+    "bubble",
+    "heatmap",
+    "calendar",
+    "histogram",
+    "box",
+    "candlestick",
+    "ohlc",
+];
+
+fn manifest_path(relative: &str) -> PathBuf {
+    Path::new(env!("CARGO_MANIFEST_DIR")).join(relative)
+}
+
+fn fixtures() -> Vec<(String, Vec<u8>)> {
+    let mut fixtures: Vec<(String, Vec<u8>)> = std::fs::read_dir(manifest_path("tests/fixtures"))
+        .expect("tests/fixtures is missing")
+        .map(|entry| entry.unwrap().path())
+        .filter(|path| path.extension().is_some_and(|ext| ext == "json"))
+        .map(|path| {
+            let name = path.file_stem().unwrap().to_string_lossy().into_owned();
+            (name, std::fs::read(&path).unwrap())
+        })
+        .collect();
+    fixtures.sort();
+    fixtures
+}
+
+fn parse(name: &str, body: &[u8]) -> GraphRequest {
+    rust_chart_worker::request::parse_json(body)
+        .unwrap_or_else(|e| panic!("{name}: invalid fixture: {}", e.to_json()))
+}
+
+fn render_png(request: &GraphRequest) -> Vec<u8> {
+    let mut request = request.clone();
+    request.format = OutputFormat::Png;
+    rust_chart_worker::render(&request)
+        .unwrap_or_else(|e| panic!("render failed: {}", e.to_json()))
+        .body
+}
+
+fn diff_dir() -> PathBuf {
+    let dir = manifest_path("target/golden-diff");
+    std::fs::create_dir_all(&dir).unwrap();
+    dir
+}
+
+fn update_requested() -> bool {
+    std::env::var("UPDATE_GOLDEN").is_ok_and(|v| v == "1")
+}
+
+// Returns a description of the first difference, if any
+fn compare_svg(expected: &str, actual: &str) -> Option<String> {
+    if expected == actual {
+        return None;
+    }
+    let expected_lines: Vec<&str> = expected.lines().collect();
+    let actual_lines: Vec<&str> = actual.lines().collect();
+    let line = (0..expected_lines.len().max(actual_lines.len()))
+        .find(|&i| expected_lines.get(i) != actual_lines.get(i))
+        .unwrap_or(0);
+    Some(format!(
+        "SVG differs at line {}:\n  expected: {}\n  actual:   {}",
+        line + 1,
+        expected_lines.get(line).unwrap_or(&"<end of file>"),
+        actual_lines.get(line).unwrap_or(&"<end of file>"),
+    ))
+}
+
+// Compares two PNGs pixel by pixel. Returns a description and a diff image (changed
+// pixels in red over a faded copy of the expected image) when they differ too much.
+fn compare_png(expected: &[u8], actual: &[u8]) -> Option<(String, Option<Vec<u8>>)> {
+    let expected = tiny_skia::Pixmap::decode_png(expected).expect("golden PNG is unreadable");
+    let actual = tiny_skia::Pixmap::decode_png(actual).expect("rendered PNG is unreadable");
+    if expected.width() != actual.width() || expected.height() != actual.height() {
+        return Some((
+            format!(
+                "PNG size changed from {}x{} to {}x{}",
+                expected.width(),
+                expected.height(),
+                actual.width(),
+                actual.height()
+            ),
+            None,
+        ));
+    }
+
+    let mut diff = expected.clone();
+    let mut changed = 0usize;
+    for ((e, a), d) in expected
+        .data()
+        .chunks_exact(4)
+        .zip(actual.data().chunks_exact(4))
+        .zip(diff.data_mut().chunks_exact_mut(4))
+    {
+        if e.iter()
+            .zip(a)
+            .any(|(x, y)| x.abs_diff(*y) > CHANNEL_TOLERANCE)
+        {
+            changed += 1;
+            d.copy_from_slice(&[255, 0, 0, 255]);
+        } else {
+            // Fade unchanged pixels so the red ones stand out
+            for channel in &mut d[..3] {
+                *channel = 255 - (255 - *channel) / 4;
+            }
+            d[3] = 255;
+        }
+    }
+
+    let ratio = changed as f64 / (expected.width() * expected.height()) as f64;
+    if ratio <= PIXEL_TOLERANCE {
+        return None;
+    }
+    Some((
+        format!(
+            "PNG differs in {} pixels ({:.3}%, tolerance {:.3}%)",
+            changed,
+            ratio * 100.0,
+            PIXEL_TOLERANCE * 100.0
+        ),
+        diff.encode_png().ok(),
+    ))
+}
+
+#[test]
+fn every_chart_type_has_a_fixture() {
+    let covered: Vec<String> = fixtures()
+        .iter()
+        .map(|(name, body)| {
+            let value: serde_json::Value = serde_json::from_slice(body).unwrap();
+            let graph_type = value["graph_type"].as_str().unwrap_or("line").to_string();
+            parse(name, body);
+            graph_type
+        })
+        .collect();
+    for graph_type in GRAPH_TYPES {
+        assert!(
+            covered.iter().any(|c| c == graph_type),
+            "no fixture in tests/fixtures uses graph_type \"{graph_type}\""
+        );
+    }
+}
+
+#[test]
+fn rendered_charts_match_golden_files() {
+    let update = update_requested();
+    let mut failures = Vec::new();
+
+    for (name, body) in fixtures() {
+        let request = parse(&name, &body);
+        let svg = rust_chart_worker::charts::render_svg(&request);
+        let png = render_png(&request);
+        let svg_path = manifest_path(&format!("tests/golden/{name}.svg"));
+        let png_path = manifest_path(&format!("tests/golden/{name}.png"));
+
+        if update {
+            std::fs::write(&svg_path, &svg).unwrap();
+            std::fs::write(&png_path, &png).unwrap();
+            continue;
+        }
+
+        let (Ok(expected_svg), Ok(expected_png)) =
+            (std::fs::read_to_string(&svg_path), std::fs::read(&png_path))
+        else {
+            failures.push(format!(
+                "{name}: golden files missing (run with UPDATE_GOLDEN=1 to create them)"
+            ));
+            continue;
+        };
+
+        let mut problems = Vec::new();
+        if let Some(problem) = compare_svg(&expected_svg, &svg) {
+            problems.push(problem);
+        }
+        if let Some((problem, diff)) = compare_png(&expected_png, &png) {
+            problems.push(problem);
+            if let Some(diff) = diff {
+                std::fs::write(diff_dir().join(format!("{name}.diff.png")), diff).unwrap();
+            }
+        }
+        if !problems.is_empty() {
+            let dir = diff_dir();
+            std::fs::write(dir.join(format!("{name}.actual.svg")), &svg).unwrap();
+            std::fs::write(dir.join(format!("{name}.actual.png")), &png).unwrap();
+            failures.push(format!("{name}: {}", problems.join("\n  ")));
+        }
+    }
+
+    assert!(
+        failures.is_empty(),
+        "{} snapshot(s) differ from tests/golden (outputs in target/golden-diff/; \
+         rerun with UPDATE_GOLDEN=1 if the change is intended):\n{}",
+        failures.len(),
+        failures.join("\n")
+    );
+}